@@ -38,24 +38,65 @@ export async function load_models() {
 
 export enum SubtitlesType {
     Online = "Online",
-    Offline = "Offline"
+    Offline = "Offline",
+    // 识别跟不上录音，msg 为丢弃的音频时长（ms），仅暂停实时识别时为 0
+    Lagging = "Lagging",
+    // 输入设备出错（如被拔出），msg 为错误信息，输入设备会自动切换
+    DeviceError = "DeviceError"
 }
 
+// SenseVoice 识别出的语言
+export type Language = "Auto" | "Chinese" | "English" | "Cantonese" | "Japanese" | "Korean" | "NoSpeech"
+
+// SenseVoice 识别出的说话人情感
+export type Emotion = "Happy" | "Sad" | "Angry" | "Neutral" | "Fearful" | "Disgusted" | "Surprised" | "Unknown"
+
+// SenseVoice 识别出的音频事件
+export type AudioEvent = "Speech" | "Bgm" | "Applause" | "Laughter" | "Cry" | "Sneeze" | "Breath" | "Cough" | "Unknown"
+
 export type Subtitles = {
     type_: SubtitlesType,
-    msg: string
+    msg: string,
+    // 声道序号，从 0 开始，仅在多声道分别识别时大于 0
+    channel: number,
+    // 语音段 id，开启音频存档时可通过 get_segment_audio 获取该语音段的音频
+    segment_id: string | null,
+    // 语音段起点（ms），从开始识别时计算
+    start: number | null,
+    // 实时识别时为已识别到的位置，离线识别时为语音段终点（ms）
+    end: number | null,
+    // 以下三项仅离线识别结果有值
+    language: Language | null,
+    emotion: Emotion | null,
+    event: AudioEvent | null,
+    // 文字是否经过逆文本正则化，识别结果以外的消息为 null
+    itn: boolean | null
 }
 
+export type Signal =
+    { Tone: { frequency: number, amplitude: number } }
+    | "Silence"
+
+/*
+识别使用的音频源，默认为当前选中的输入设备
+ */
+export type AudioSourceConfig =
+    { type: "Microphone" }
+    | { type: "File", path: string }
+    | { type: "Stdin", format: "I16" | "F32", sample_rate: number }
+    | { type: "Signal", signal: Signal, duration?: number }
+
 /**
  * 实时语音识别
  */
-export async function start_speech_recognition(callback: (subtitles: Subtitles) => void) {
+export async function start_speech_recognition(callback: (subtitles: Subtitles) => void, source?: AudioSourceConfig) {
     const onEvent = new Channel<Subtitles>();
     onEvent.onmessage = (subtitles) => {
         callback(subtitles)
     }
     await invoke("start_speech_recognition", {
-        "onEvent": onEvent
+        "onEvent": onEvent,
+        "source": source
     })
 }
 
@@ -64,4 +105,237 @@ export async function start_speech_recognition(callback: (subtitles: Subtitles)
  */
 export async function stop_speech_recognition() {
     await invoke("stop_speech_recognition")
-}
\ No newline at end of file
+}
+export type TranscribedSegment = {
+    channel: number,
+    start: number,
+    end: number,
+    text: string,
+    language: Language | null,
+    emotion: Emotion | null,
+    event: AudioEvent | null,
+    itn: boolean
+}
+
+/**
+ * 离线识别音频文件
+ * channelMode 为 PerChannel 时每个声道单独识别
+ */
+export async function transcribe_file(path: string, channelMode?: ChannelMode): Promise<TranscribedSegment[]> {
+    return invoke<TranscribedSegment[]>("transcribe_file", {
+        "path": path,
+        "channelMode": channelMode
+    })
+}
+
+/*
+多声道输入设备的处理方式
+Select 为声道序号，从 0 开始
+ */
+export type ChannelMode = "Downmix" | { Select: number } | "PerChannel"
+
+/**
+ * 设置多声道输入设备的处理方式
+ */
+export async function set_channel_mode(channelMode: ChannelMode) {
+    await invoke("set_channel_mode", {
+        "channelMode": channelMode
+    })
+}
+
+/*
+识别速度跟不上录音时的处理方式
+DropOldest: 丢弃最早的音频
+DropNewest: 丢弃新录制的音频
+SkipOnline: 暂停实时识别直到追上进度
+ */
+export type OverflowPolicy = "DropOldest" | "DropNewest" | "SkipOnline"
+
+/**
+ * 设置识别速度跟不上录音时的处理方式
+ */
+export async function set_overflow_policy(overflowPolicy: OverflowPolicy) {
+    await invoke("set_overflow_policy", {
+        "overflowPolicy": overflowPolicy
+    })
+}
+
+export type DeviceConfig = {
+    channels: number,
+    sample_rate: number,
+    sample_format: string
+}
+
+export type DeviceConfigRange = {
+    channels: number,
+    min_sample_rate: number,
+    max_sample_rate: number,
+    sample_format: string
+}
+
+export type DeviceConfigs = {
+    name: string,
+    default_config: DeviceConfig | null,
+    supported_configs: DeviceConfigRange[],
+    // 录音时实际使用的配置
+    best_config: DeviceConfig | null,
+    error: string | null
+}
+
+/**
+ * 列出所有输入设备支持的配置，用于排查设备问题
+ */
+export async function list_device_configs(): Promise<DeviceConfigs[]> {
+    return invoke<DeviceConfigs[]>("list_device_configs")
+}
+
+/*
+识别音频的保存方式
+Continuous: 每个声道的全部音频保存为一个文件
+PerSegment: 每个语音段保存为一个文件
+ */
+export type ArchiveMode = "Disabled" | "Continuous" | "PerSegment"
+
+/**
+ * 设置识别音频的保存方式，下次开始识别时生效
+ * archiveDir 为空时不修改保存目录
+ */
+export async function set_archive_mode(archiveMode: ArchiveMode, archiveDir?: string) {
+    await invoke("set_archive_mode", {
+        "archiveMode": archiveMode,
+        "archiveDir": archiveDir
+    })
+}
+
+/*
+倒谱均值方差归一化的方式
+Global: 使用模型训练集的全局统计量
+Online: 使用最近 window 帧(10ms/帧)的统计量，prior_weight 为全局统计量相当于的帧数
+ */
+export type CmvnMode = "Global" | { Online: { window?: number, prior_weight?: number } }
+
+export type CmvnConfig = {
+    vad: CmvnMode,
+    recognition: CmvnMode
+}
+
+/**
+ * 设置 VAD 与语音识别前端的 CMVN 方式，下次开始识别时生效
+ */
+export async function set_cmvn_config(cmvn: CmvnConfig) {
+    await invoke("set_cmvn_config", {
+        "cmvn": cmvn
+    })
+}
+
+/*
+语音端点检测的灵敏度
+Low: 适用于嘈杂环境，减少误检并尽快结束语音段
+Medium: 使用模型目录中的配置
+High: 适用于音量较小的说话人
+Custom: 自定义配置
+ */
+export type VadSensitivity = "Low" | "Medium" | "High" | "Custom"
+
+/*
+语音端点检测配置，时间单位均为 ms
+detect_mode: 0 只检测一个语音段，1 持续检测多个语音段
+ */
+export type VadConfig = {
+    detect_mode: 0 | 1,
+    max_end_silence_time: number,
+    max_start_silence_time: number,
+    max_single_segment_time: number,
+    window_size_ms: number,
+    sil_to_speech_time_thres: number,
+    speech_to_sil_time_thres: number,
+    lookback_time_start_point: number,
+    lookahead_time_end_point: number,
+    speech_2_noise_ratio: number,
+    snr_thres: number,
+    noise_frame_num_used_for_snr: number,
+    decibel_thres: number,
+    speech_noise_thres: number
+}
+
+export type VadSettings = {
+    sensitivity: VadSensitivity,
+    config: VadConfig
+}
+
+/**
+ * 获取语音端点检测的灵敏度与当前配置
+ */
+export async function get_vad_settings(): Promise<VadSettings> {
+    return invoke<VadSettings>("get_vad_settings")
+}
+
+/**
+ * 设置语音端点检测的灵敏度，正在进行的识别立即生效
+ */
+export async function set_vad_sensitivity(sensitivity: VadSensitivity) {
+    await invoke("set_vad_sensitivity", {
+        "sensitivity": sensitivity
+    })
+}
+
+/**
+ * 设置自定义的语音端点检测配置，正在进行的识别立即生效
+ */
+export async function set_vad_config(vadConfig: VadConfig) {
+    await invoke("set_vad_config", {
+        "vadConfig": vadConfig
+    })
+}
+
+/**
+ * 设置是否将识别结果中的数字、日期、金额转为阿拉伯数字，正在进行的识别立即生效
+ */
+export async function set_itn(itn: boolean) {
+    await invoke("set_itn", {
+        "itn": itn
+    })
+}
+
+/*
+字幕的显示方式
+Plain: 只显示识别的文字
+Sdh: 听障字幕，在文字前以 [掌声]、[Music] 的形式显示音频事件
+ */
+export type CaptionMode = "Plain" | "Sdh"
+
+/**
+ * 设置字幕的显示方式，正在进行的识别立即生效
+ */
+export async function set_caption_mode(captionMode: CaptionMode) {
+    await invoke("set_caption_mode", {
+        "captionMode": captionMode
+    })
+}
+
+/*
+语音端点检测模型
+Fsmn: FunASR 的 FSMN 模型 vad.onnx
+Silero: Silero 模型 silero-vad.onnx，需要自行放入 models 文件夹
+Energy: 基于音量与过零率检测，不需要模型，模型加载失败时自动使用
+ */
+export type VadModel = "Fsmn" | "Silero" | "Energy"
+
+/**
+ * 设置语音端点检测模型，下次开始识别时生效
+ */
+export async function set_vad_model(vadModel: VadModel) {
+    await invoke("set_vad_model", {
+        "vadModel": vadModel
+    })
+}
+
+/**
+ * 获取已保存语音段的 WAV 音频，用于回放
+ */
+export async function get_segment_audio(segmentId: string): Promise<Blob> {
+    const data = await invoke<number[]>("get_segment_audio", {
+        "segmentId": segmentId
+    })
+    return new Blob([new Uint8Array(data)], {type: "audio/wav"})
+}
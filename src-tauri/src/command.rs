@@ -1,18 +1,31 @@
+use crate::funasr::utils::constant::{
+    FBANK, MAX_SENSE_VOICE_FRAMES, SAMPLE_RATE, SPLIT_LOOKBACK_FRAMES,
+};
 use crate::funasr::utils::download;
 use crate::funasr::{
-    pretreatment, Cache, Frame, PointType, ENDPOINT_DETECTION_WAV_FRONTEND,
-    SPEECH_RECOGNITION_WAV_FRONTEND,
+    default_device, device_configs, find_split_point, input_channels, pretreatment,
+    read_archived_segment, ArchiveMode, AudioArchive, AudioEvent, AudioSource, Cache, CaptionMode,
+    ChannelMode, CmvnConfig, DeviceConfigs, Emotion, FileSource, Frame, Language, LookbackFrames,
+    OverflowPolicy, PcmFormat, PointType, Signal, SignalSource, StdinSource, StreamingFrontend,
+    StreamingItn, TranscribedSegment, VadBackend, VadConfig, VadModel, VadSensitivity,
+    VoiceDetector, SPEECH_RECOGNITION_WAV_FRONTEND,
 };
 use crate::global::{
-    init_lazy_lock, PARAFORMER, RECORDER, SENSE_VOICE, STOP_SPEECH_RECOGNITION, VAD,
+    get_device_by_name, init_lazy_lock, load_vad_backend, open_recorder, vad_backend, CONFIG,
+    PARAFORMER, RECORDER, SENSE_VOICE, STOP_SPEECH_RECOGNITION,
+};
+use crate::tray_icon::{
+    all_input_devices, apply_itn, apply_vad_sensitivity, check_itn_menu, check_vad_sensitivity_menu,
 };
 use anyhow::Result;
-use log::info;
-use ndarray::Array1;
+use log::{error, info, warn};
+use ndarray::{concatenate, Array1, Array2, ArrayView2, Axis};
 use serde::{Deserialize, Serialize};
 use std::mem::take;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tauri::ipc::Channel;
+use tauri::AppHandle;
 use tokio::time;
 
 /// 检查模型文件是否存在
@@ -52,37 +65,174 @@ pub async fn init() -> Result<(), String> {
 pub enum SubtitlesType {
     Online = 1,
     Offline = 2,
+    /// 识别跟不上录音，`msg` 为丢弃的音频时长（ms），仅暂停实时识别时为 0
+    Lagging = 3,
+    /// 音频源出错（如输入设备被拔出），`msg` 为错误信息，输入设备会自动切换
+    DeviceError = 4,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Subtitles {
     pub type_: SubtitlesType,
     pub msg: String,
+    /// 声道序号，从 0 开始，仅在多声道分别识别时大于 0
+    pub channel: usize,
+    /// 语音段 id，开启音频存档时用于获取该语音段的音频
+    pub segment_id: Option<String>,
+    /// 语音段起点，单位：ms，从开始识别时计算
+    pub start: Option<u64>,
+    /// 实时识别时为已识别到的位置，离线识别时为语音段终点，单位：ms
+    pub end: Option<u64>,
+    /// SenseVoice 识别出的语言，仅离线识别结果
+    pub language: Option<Language>,
+    /// SenseVoice 识别出的说话人情感，仅离线识别结果
+    pub emotion: Option<Emotion>,
+    /// SenseVoice 识别出的音频事件，仅离线识别结果
+    pub event: Option<AudioEvent>,
+    /// 文字是否经过逆文本正则化，识别结果以外的消息为 None
+    pub itn: Option<bool>,
 }
 
-#[tauri::command]
-pub async fn start_speech_recognition(on_event: Channel<Subtitles>) -> Result<(), String> {
-    // 音频数据缓存
+impl Subtitles {
+    /// 创建消息，语音段 id 与 SenseVoice 识别出的信息为空
+    pub fn new(
+        channel: usize,
+        type_: SubtitlesType,
+        msg: String,
+        start: Option<u64>,
+        end: Option<u64>,
+    ) -> Self {
+        Self {
+            type_,
+            msg,
+            channel,
+            segment_id: None,
+            start,
+            end,
+            language: None,
+            emotion: None,
+            event: None,
+            itn: None,
+        }
+    }
+}
 
-    let mut recorder_waveform: bool = false; // 是否缓存录音数据
-    let mut last_300ms: Vec<Frame> = Vec::new(); // 缓存最后30帧
-    let mut remaining_waveform = Array1::<f32>::zeros(0); // 录音数据转为音频帧时剩余音频数据
+/// 识别使用的音频源
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum AudioSourceConfig {
+    /// 当前选中的输入设备
+    Microphone,
+    /// 音频文件
+    File { path: String },
+    /// 标准输入中的原始 PCM 数据
+    Stdin { format: PcmFormat, sample_rate: u32 },
+    /// 合成信号，`duration` 单位为 ms，为空时无限生成
+    Signal {
+        signal: Signal,
+        duration: Option<u64>,
+    },
+}
 
-    let mut vad_remaining_frames: Vec<Frame> = Vec::new(); // 缓存 vad 提取特征剩余音频帧
+impl AudioSourceConfig {
+    /// 创建音频源
+    fn open(self) -> Result<Arc<Mutex<dyn AudioSource>>> {
+        let source: Arc<Mutex<dyn AudioSource>> = match self {
+            AudioSourceConfig::Microphone => RECORDER.clone(),
+            AudioSourceConfig::File { path } => Arc::new(Mutex::new(FileSource::new(path)?)),
+            AudioSourceConfig::Stdin {
+                format,
+                sample_rate,
+            } => Arc::new(Mutex::new(StdinSource::new(format, sample_rate)?)),
+            AudioSourceConfig::Signal { signal, duration } => Arc::new(Mutex::new(
+                SignalSource::new(signal, duration.map(Duration::from_millis)),
+            )),
+        };
+        Ok(source)
+    }
+}
 
-    let mut paraformer_frames: Vec<Frame> = Vec::new(); // 为 paraformer 储存音频帧
-    let mut cache = Cache::default(); // 缓存 paraformer 的中间结果
-    let mut paraformer_remaining_frames: Vec<Frame> = Vec::new();
+/// 实时语音识别
+/// # 参数
+/// - `source`: 音频源，默认为当前选中的输入设备
+#[tauri::command]
+pub async fn start_speech_recognition(
+    on_event: Channel<Subtitles>,
+    source: Option<AudioSourceConfig>,
+) -> Result<(), String> {
+    *STOP_SPEECH_RECOGNITION.lock().unwrap() = false;
+    let source = source
+        .unwrap_or(AudioSourceConfig::Microphone)
+        .open()
+        .map_err(|e| e.to_string())?;
+    speech_recognition(source, &on_event).await
+}
 
-    let mut sense_voice_frames: Vec<Frame> = Vec::new(); // 为 sense_voice 储存音频帧
+/// 从音频源中读取音频并进行识别，直到收到停止信号或音频源结束
+/// 多声道音频源的每个声道使用独立的识别流
+async fn speech_recognition(
+    source: Arc<Mutex<dyn AudioSource>>,
+    on_event: &Channel<Subtitles>,
+) -> Result<(), String> {
+    let mut recognizers: Vec<ChannelRecognizer> = Vec::new();
+    let mut skipping_online = false; // 是否正在跳过实时识别
+    let (mut archive, cmvn, mut vad_config, vad_model) = {
+        let config = CONFIG.lock().unwrap();
+        let archive = match config.archive_mode {
+            ArchiveMode::Disabled => None,
+            mode => Some(AudioArchive::new(&config.archive_dir, mode).map_err(|e| e.to_string())?),
+        };
+        (
+            archive,
+            config.cmvn,
+            config.vad_config.clone(),
+            config.vad_model,
+        )
+    };
+    let vad_backend = vad_backend(vad_model);
+    source.lock().unwrap().attach();
 
     loop {
         // 第一步：尝试获取音频数据（限制锁的作用域）
-        let audio_data = {
-            let mut recorder = RECORDER.lock().unwrap();
-            recorder.pop_head_sample(1600)
+        let (audio_data, finished, dropped, lagging, error) = {
+            let mut source = source.lock().unwrap();
+            (
+                source.pop_head_channels(1600),
+                source.is_finished(),
+                source.take_dropped(),
+                source.is_lagging(),
+                source.take_error(),
+            )
         }; // 锁在这里释放
 
+        if let Some(error) = error {
+            on_event
+                .send(Subtitles::new(
+                    0,
+                    SubtitlesType::DeviceError,
+                    error,
+                    None,
+                    None,
+                ))
+                .map_err(|e| e.to_string())?;
+        }
+
+        // 识别跟不上录音时通知前端，避免字幕无提示地跳跃
+        if dropped > 0 || (lagging && !skipping_online) {
+            let dropped_ms = dropped * 1000 / SAMPLE_RATE;
+            warn!("识别跟不上录音，丢弃音频 {}ms", dropped_ms);
+            on_event
+                .send(Subtitles::new(
+                    0,
+                    SubtitlesType::Lagging,
+                    dropped_ms.to_string(),
+                    None,
+                    None,
+                ))
+                .map_err(|e| e.to_string())?;
+        }
+        skipping_online = lagging;
+
         if let Some(audio_data) = audio_data {
             // 检查退出标志
             let should_exit = {
@@ -94,94 +244,318 @@ pub async fn start_speech_recognition(on_event: Channel<Subtitles>) -> Result<()
                 break;
             }
 
-            // 提取音频帧
-            let (frames, remaining_waveform_tmp) = pretreatment(audio_data, remaining_waveform);
-            remaining_waveform = remaining_waveform_tmp;
+            // 端点检测配置修改后立即应用到所有识别流
+            let latest_vad_config = CONFIG.lock().unwrap().vad_config.clone();
+            if latest_vad_config != vad_config {
+                info!("端点检测配置已修改");
+                vad_config = latest_vad_config;
+                for recognizer in recognizers.iter_mut() {
+                    recognizer.vad.set_config(vad_config.clone());
+                    recognizer.lookback.set_config(&vad_config);
+                }
+            }
 
-            // 如果处于录音状态 则缓存
-            if recorder_waveform {
-                sense_voice_frames.extend(frames.clone());
-                paraformer_frames.extend(frames.clone());
+            // 声道数变化（如切换输入设备）时重新创建识别流
+            if recognizers.len() != audio_data.len() {
+                info!("音频源声道数: {}", audio_data.len());
+                recognizers = (0..audio_data.len())
+                    .map(|channel| ChannelRecognizer::new(channel, cmvn, &vad_config, &vad_backend))
+                    .collect::<Result<_>>()
+                    .map_err(|e| e.to_string())?;
+            }
+            for (recognizer, channel_data) in recognizers.iter_mut().zip(audio_data) {
+                recognizer.process(channel_data, skipping_online, &mut archive, on_event)?;
             }
-            // 提取VAD特征 将音频帧转为特征向量
-            vad_remaining_frames.extend(frames.clone()); // 将本次帧加入缓存
-            let (features, vad_remaining_frames_tmp) =
-                ENDPOINT_DETECTION_WAV_FRONTEND.extract_features(&vad_remaining_frames);
-
-            last_300ms.extend(frames.clone());
-            if last_300ms.len() > 30 {
-                // 只保留最新的300ms音频
-                last_300ms.drain(0..last_300ms.len() - 30);
+        } else if finished {
+            // 音频源结束时仍处于语音段中，则识别剩余部分
+            for recognizer in recognizers.iter_mut() {
+                recognizer.finish(&mut archive, on_event)?;
+            }
+            info!("音频源已结束");
+            break;
+        } else {
+            // 没有音频数据时等待音频源写入，音频源被替换时也会唤醒
+            // 不支持通知或已经不会再写入的音频源则等待100ms
+            let notifier = source.lock().unwrap().notifier();
+            let readable = match notifier {
+                Some(notifier) => notifier.readable(1600).await,
+                None => false,
+            };
+            if !readable {
+                time::sleep(Duration::from_millis(100)).await;
             }
+        }
+    }
 
-            let segments = {
-                VAD.lock()
+    Ok(())
+}
+
+/// paraformer 实时识别每次输入的特征行数，600ms
+const PARAFORMER_CHUNK_ROWS: usize = 10;
+
+/// 单个声道的识别流
+/// 保存该声道的音频帧缓存、VAD 状态以及 paraformer 的中间结果
+/// 音频帧以 `Arc` 在各个缓存间共享
+struct ChannelRecognizer {
+    channel: usize,                        // 声道序号
+    recorder_waveform: bool,               // 是否缓存录音数据
+    lookback: LookbackFrames,              // 语音起点之前的音频帧
+    remaining_waveform: Array1<f32>,       // 录音数据转为音频帧时剩余音频数据
+    vad: Box<dyn VoiceDetector>,           // 语音端点检测器
+    speech_frontend: StreamingFrontend,    // paraformer 与 sense_voice 共用的流式前端
+    paraformer_features: Vec<Array2<f32>>, // 等待 paraformer 实时识别的特征
+    cache: Cache,                          // 缓存 paraformer 的中间结果
+    online_itn: StreamingItn,              // paraformer 实时识别结果的逆文本正则化
+    sense_voice_frames: Vec<Arc<Frame>>,   // 为 sense_voice 储存音频帧
+    segment_id: Option<String>,            // 当前语音段在音频存档中的 id
+    segment_start: u64,                    // 当前语音段的起点，单位：ms
+    frame_count: usize,                    // 已提取的音频帧数
+}
+
+impl ChannelRecognizer {
+    fn new(
+        channel: usize,
+        cmvn: CmvnConfig,
+        vad_config: &VadConfig,
+        vad_backend: &VadBackend,
+    ) -> Result<Self> {
+        Ok(Self {
+            channel,
+            recorder_waveform: false,
+            lookback: LookbackFrames::new(vad_config, FBANK.options().frame_shift_ms),
+            remaining_waveform: Array1::<f32>::zeros(0),
+            vad: vad_backend.detector(cmvn.vad, vad_config.clone())?,
+            speech_frontend: StreamingFrontend::new(
+                &SPEECH_RECOGNITION_WAV_FRONTEND,
+                cmvn.recognition,
+            ),
+            paraformer_features: Vec::new(),
+            cache: Cache::default(),
+            online_itn: StreamingItn::default(),
+            sense_voice_frames: Vec::new(),
+            segment_id: None,
+            segment_start: 0,
+            frame_count: 0,
+        })
+    }
+
+    /// 丢弃 paraformer 实时识别的状态
+    fn reset_paraformer(&mut self) {
+        self.speech_frontend.reset();
+        take(&mut self.paraformer_features);
+        self.cache = Cache::default();
+        self.online_itn.reset();
+    }
+
+    /// 处理该声道的一块音频数据
+    /// `skip_online` 为 true 时不进行 paraformer 实时识别，语音段结束后仍会输出完整的识别结果
+    fn process(
+        &mut self,
+        audio_data: Vec<f32>,
+        skip_online: bool,
+        archive: &mut Option<AudioArchive>,
+        on_event: &Channel<Subtitles>,
+    ) -> Result<(), String> {
+        if let Some(archive) = archive {
+            if let Err(e) = archive.write(self.channel, &audio_data) {
+                error!("保存音频失败: {}", e);
+            }
+        }
+        // 提取音频帧
+        let (frames, remaining_waveform) =
+            pretreatment(&FBANK, &audio_data, take(&mut self.remaining_waveform));
+        self.remaining_waveform = remaining_waveform;
+        self.frame_count += frames.len();
+
+        // 如果处于录音状态 则缓存
+        if self.recorder_waveform {
+            self.sense_voice_frames.extend(frames.iter().cloned());
+            self.accept_paraformer_frames(&frames);
+        }
+        let segments = self
+            .vad
+            .detect(&audio_data, &frames)
+            .map_err(|e| e.to_string())?;
+
+        self.lookback.push(&frames);
+
+        for segment in segments {
+            match segment.segment_type {
+                PointType::Start => {
+                    self.segment_start = segment.start;
+                    // 语音段从缓存的语音起点开始
+                    let (_, lookback) = self.lookback.take_from(segment.start);
+                    self.start_archive_segment(archive, lookback.len());
+                    self.recorder_waveform = true;
+                    self.reset_paraformer();
+                    self.accept_paraformer_frames(&lookback);
+                    self.sense_voice_frames = lookback;
+                }
+                PointType::End => {
+                    self.recorder_waveform = false;
+                    let end = segment.end.unwrap_or_else(|| self.vad.elapsed_ms());
+                    self.send_offline_subtitles(end, 0, archive, on_event)?;
+                    take(&mut self.sense_voice_frames);
+                    self.reset_paraformer();
+                }
+            }
+        }
+        if self.recorder_waveform && self.sense_voice_frames.len() >= MAX_SENSE_VOICE_FRAMES {
+            self.split_segment(archive, on_event)?;
+        }
+
+        let paraformer_rows: usize = self
+            .paraformer_features
+            .iter()
+            .map(|features| features.nrows())
+            .sum();
+        if self.recorder_waveform && skip_online {
+            // 丢弃积压期间的实时识别状态，追上进度后重新开始
+            self.reset_paraformer();
+        } else if self.recorder_waveform && paraformer_rows >= PARAFORMER_CHUNK_ROWS {
+            let features = {
+                let views: Vec<ArrayView2<f32>> =
+                    self.paraformer_features.iter().map(|f| f.view()).collect();
+                concatenate(Axis(0), &views).map_err(|e| e.to_string())?
+            };
+            take(&mut self.paraformer_features);
+            let word = {
+                PARAFORMER
+                    .lock()
                     .unwrap()
-                    .call(features, &vad_remaining_frames)
+                    .call(features, &mut self.cache)
                     .map_err(|e| e.to_string())?
             };
+            // paraformer 没有逆文本正则化，使用规则转换数字
+            let itn = CONFIG.lock().unwrap().itn;
+            let word = if itn {
+                self.online_itn.accept(&word)
+            } else {
+                self.online_itn.finish() + &word
+            };
+            on_event
+                .send(Subtitles {
+                    segment_id: self.segment_id.clone(),
+                    itn: Some(itn),
+                    ..Subtitles::new(
+                        self.channel,
+                        SubtitlesType::Online,
+                        word,
+                        Some(self.segment_start),
+                        Some(self.vad.elapsed_ms()),
+                    )
+                })
+                .expect("发送消息事件失败");
+        }
+        Ok(())
+    }
 
-            vad_remaining_frames = vad_remaining_frames_tmp; // 缓存剩余的帧
-            for segment in segments {
-                match segment.segment_type {
-                    PointType::Start => {
-                        recorder_waveform = true;
-                        paraformer_frames = last_300ms.clone();
-                        sense_voice_frames = take(&mut last_300ms);
-                    }
-                    PointType::End => {
-                        recorder_waveform = false;
-                        let (features, _) =
-                            SPEECH_RECOGNITION_WAV_FRONTEND.extract_features(&sense_voice_frames);
-                        let result = {
-                            SENSE_VOICE
-                                .lock()
-                                .unwrap()
-                                .call(features)
-                                .map_err(|e| e.to_string())?
-                        };
-
-                        on_event
-                            .send(Subtitles {
-                                type_: SubtitlesType::Offline,
-                                msg: result,
-                            })
-                            .map_err(|e| e.to_string())?;
-                        take(&mut paraformer_frames);
-                        take(&mut paraformer_remaining_frames);
-                        take(&mut sense_voice_frames);
-                        cache = Cache::default();
-                    }
-                }
-            }
+    /// 将音频帧送入 paraformer 的流式前端，缓存新增的特征
+    fn accept_paraformer_frames(&mut self, frames: &[Arc<Frame>]) {
+        let (features, _) = self.speech_frontend.accept(frames);
+        if features.nrows() > 0 {
+            self.paraformer_features.push(features);
+        }
+    }
 
-            if recorder_waveform && paraformer_frames.len() > 60 {
-                paraformer_remaining_frames.extend(paraformer_frames);
-                let (features, paraformer_remaining_frames_tmp) =
-                    SPEECH_RECOGNITION_WAV_FRONTEND.extract_features(&paraformer_remaining_frames);
-                paraformer_remaining_frames = paraformer_remaining_frames_tmp;
-                let word = {
-                    PARAFORMER
-                        .lock()
-                        .unwrap()
-                        .call(features, &mut cache)
-                        .map_err(|e| e.to_string())?
-                };
-                on_event
-                    .send(Subtitles {
-                        type_: SubtitlesType::Online,
-                        msg: word,
-                    })
-                    .expect("发送消息事件失败");
-                paraformer_frames = Vec::new();
+    /// 开启音频存档时开始一个语音段
+    /// `lookback_frames` 为语音起点之后已提取的音频帧数，之后的音频还有未转为音频帧的部分
+    fn start_archive_segment(
+        &mut self,
+        archive: &mut Option<AudioArchive>,
+        lookback_frames: usize,
+    ) {
+        if let Some(archive) = archive {
+            let lookback = lookback_frames * FBANK.frame_shift() + self.remaining_waveform.len();
+            match archive.start_segment(self.channel, lookback) {
+                Ok(segment_id) => self.segment_id = Some(segment_id),
+                Err(e) => error!("保存语音段失败: {}", e),
             }
-        } else {
-            // 没有音频数据则等待100ms
-            time::sleep(Duration::from_millis(100)).await;
         }
     }
 
-    Ok(())
+    /// 语音段过长时在末尾能量最低的帧处切分
+    /// 识别切分点之前的部分，之后的音频帧作为新语音段的开头继续识别
+    fn split_segment(
+        &mut self,
+        archive: &mut Option<AudioArchive>,
+        on_event: &Channel<Subtitles>,
+    ) -> Result<(), String> {
+        let split = find_split_point(&self.sense_voice_frames, SPLIT_LOOKBACK_FRAMES);
+        let tail = self.sense_voice_frames.split_off(split);
+        let end_frame = self.frame_count - tail.len();
+        let end = (end_frame as f32 * FBANK.options().frame_shift_ms) as u64;
+        info!("语音段过长，在 {}ms 处切分", end);
+        self.send_offline_subtitles(end, tail.len(), archive, on_event)?;
+        self.segment_start = end;
+        self.start_archive_segment(archive, tail.len());
+        self.reset_paraformer();
+        self.accept_paraformer_frames(&tail);
+        self.sense_voice_frames = tail;
+        Ok(())
+    }
+
+    /// 音频源结束时仍处于语音段中，则识别剩余部分
+    fn finish(
+        &mut self,
+        archive: &mut Option<AudioArchive>,
+        on_event: &Channel<Subtitles>,
+    ) -> Result<(), String> {
+        if self.recorder_waveform {
+            self.recorder_waveform = false;
+            let end = self.vad.elapsed_ms();
+            self.send_offline_subtitles(end, 0, archive, on_event)?;
+        }
+        Ok(())
+    }
+
+    /// 使用 SenseVoice 识别完整语音段并发送字幕，开启音频存档时同时结束该语音段
+    /// `end` 为语音段终点，单位：ms
+    /// `lookback_frames` 为语音终点之后已提取的音频帧数
+    fn send_offline_subtitles(
+        &mut self,
+        end: u64,
+        lookback_frames: usize,
+        archive: &mut Option<AudioArchive>,
+        on_event: &Channel<Subtitles>,
+    ) -> Result<(), String> {
+        // 语音段的音频帧已送入流式前端，在线 CMVN 的统计量中包含该语音段
+        let features = self
+            .speech_frontend
+            .extract_features(&self.sense_voice_frames);
+        let result = {
+            SENSE_VOICE
+                .lock()
+                .unwrap()
+                .call(features)
+                .map_err(|e| e.to_string())?
+        };
+        let caption = result.caption(CONFIG.lock().unwrap().caption_mode);
+        let segment_id = take(&mut self.segment_id);
+        if let Some(archive) = archive {
+            // 语音段终点之后还有未转为音频帧的音频
+            let lookback = lookback_frames * FBANK.frame_shift() + self.remaining_waveform.len();
+            if let Err(e) = archive.end_segment(self.channel, lookback, &caption) {
+                error!("保存语音段失败: {}", e);
+            }
+        }
+        on_event
+            .send(Subtitles {
+                segment_id,
+                language: result.language,
+                emotion: result.emotion,
+                event: result.event,
+                itn: Some(result.itn),
+                ..Subtitles::new(
+                    self.channel,
+                    SubtitlesType::Offline,
+                    caption,
+                    Some(self.segment_start),
+                    Some(end),
+                )
+            })
+            .map_err(|e| e.to_string())
+    }
 }
 
 #[tauri::command]
@@ -189,3 +563,167 @@ pub fn stop_speech_recognition() {
     let mut flag = STOP_SPEECH_RECOGNITION.lock().unwrap();
     *flag = true;
 }
+
+/// 离线识别音频文件
+/// 支持 WAV/FLAC/MP3/OGG 格式，返回所有语音段及其起止时间
+/// # 参数
+/// - `channel_mode`: 多声道音频的处理方式，默认合并为单声道，`PerChannel` 时每个声道单独识别
+#[tauri::command]
+pub async fn transcribe_file(
+    path: String,
+    channel_mode: Option<ChannelMode>,
+) -> Result<Vec<TranscribedSegment>, String> {
+    let channel_mode = channel_mode.unwrap_or_default();
+    let (cmvn, vad_config, vad_model, caption_mode) = {
+        let config = CONFIG.lock().unwrap();
+        (
+            config.cmvn,
+            config.vad_config.clone(),
+            config.vad_model,
+            config.caption_mode,
+        )
+    };
+    let vad_backend = vad_backend(vad_model);
+    tokio::task::spawn_blocking(move || {
+        crate::funasr::transcribe_file(
+            path,
+            channel_mode,
+            cmvn,
+            &vad_config,
+            &vad_backend,
+            &SENSE_VOICE,
+            caption_mode,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// 设置多声道输入设备的处理方式，并使用新的方式重新打开当前输入设备
+/// 选择的声道超出当前输入设备的声道数时返回错误，不修改设置
+#[tauri::command]
+pub fn set_channel_mode(channel_mode: ChannelMode) -> Result<(), String> {
+    // 先释放 CONFIG 的锁，RECORDER 初始化时需要获取 CONFIG 的锁
+    let mut config = CONFIG.lock().unwrap().clone();
+    let device = config
+        .select_device_name
+        .clone()
+        .and_then(get_device_by_name)
+        .or_else(default_device);
+    if let Some(device) = device {
+        let channels = input_channels(&device).map_err(|e| e.to_string())?;
+        channel_mode.validate(channels).map_err(|e| e.to_string())?;
+    }
+    CONFIG.lock().unwrap().channel_mode = channel_mode;
+    config.channel_mode = channel_mode;
+    *RECORDER.lock().unwrap() = open_recorder(&config);
+    Ok(())
+}
+
+/// 设置识别速度跟不上录音时的处理方式
+#[tauri::command]
+pub fn set_overflow_policy(overflow_policy: OverflowPolicy) {
+    CONFIG.lock().unwrap().overflow_policy = overflow_policy;
+    RECORDER
+        .lock()
+        .unwrap()
+        .set_overflow_policy(overflow_policy);
+}
+
+/// 列出所有输入设备支持的配置，用于排查设备问题
+#[tauri::command]
+pub fn list_device_configs() -> Vec<DeviceConfigs> {
+    let channel_mode = CONFIG.lock().unwrap().channel_mode;
+    all_input_devices()
+        .iter()
+        .map(|device| device_configs(device, channel_mode))
+        .collect()
+}
+
+/// 设置识别音频的保存方式，下次开始识别时生效
+/// # 参数
+/// - `archive_dir`: 保存目录，为空时不修改
+#[tauri::command]
+pub fn set_archive_mode(archive_mode: ArchiveMode, archive_dir: Option<String>) {
+    let mut config = CONFIG.lock().unwrap();
+    config.archive_mode = archive_mode;
+    if let Some(archive_dir) = archive_dir {
+        config.archive_dir = archive_dir.into();
+    }
+}
+
+/// 设置 VAD 与语音识别前端的 CMVN 方式，下次开始识别时生效
+/// 远场或低质量麦克风的特征分布与训练数据差异较大时，可以使用在线 CMVN
+#[tauri::command]
+pub fn set_cmvn_config(cmvn: CmvnConfig) {
+    CONFIG.lock().unwrap().cmvn = cmvn;
+}
+
+/// 语音端点检测的灵敏度与配置
+#[derive(Serialize, Deserialize)]
+pub struct VadSettings {
+    pub sensitivity: VadSensitivity,
+    pub config: VadConfig,
+}
+
+/// 获取语音端点检测的灵敏度与当前配置
+#[tauri::command]
+pub fn get_vad_settings() -> VadSettings {
+    let config = CONFIG.lock().unwrap();
+    VadSettings {
+        sensitivity: config.vad_sensitivity,
+        config: config.vad_config.clone(),
+    }
+}
+
+/// 设置语音端点检测的灵敏度，正在进行的识别立即生效
+/// 预设在模型目录的端点检测配置上调整，`Custom` 时保留当前配置
+#[tauri::command]
+pub fn set_vad_sensitivity(app: AppHandle, sensitivity: VadSensitivity) {
+    apply_vad_sensitivity(sensitivity);
+    check_vad_sensitivity_menu(&app, sensitivity);
+}
+
+/// 设置自定义的语音端点检测配置，正在进行的识别立即生效
+#[tauri::command]
+pub fn set_vad_config(app: AppHandle, vad_config: VadConfig) -> Result<(), String> {
+    vad_config.validate().map_err(|e| e.to_string())?;
+    {
+        let mut config = CONFIG.lock().unwrap();
+        config.vad_sensitivity = VadSensitivity::Custom;
+        config.vad_config = vad_config;
+    }
+    check_vad_sensitivity_menu(&app, VadSensitivity::Custom);
+    Ok(())
+}
+
+/// 设置是否将识别结果中的数字、日期、金额转为阿拉伯数字，正在进行的识别立即生效
+#[tauri::command]
+pub fn set_itn(app: AppHandle, itn: bool) {
+    apply_itn(itn);
+    check_itn_menu(&app, itn);
+}
+
+/// 设置字幕的显示方式，正在进行的识别立即生效
+#[tauri::command]
+pub fn set_caption_mode(caption_mode: CaptionMode) {
+    CONFIG.lock().unwrap().caption_mode = caption_mode;
+}
+
+/// 设置语音端点检测模型，下次开始识别时生效
+/// 选择 `VadModel::Energy` 可以在低功耗设备上跳过神经网络推理
+#[tauri::command]
+pub fn set_vad_model(vad_model: VadModel) -> Result<(), String> {
+    // 确认模型可以加载
+    load_vad_backend(vad_model)?;
+    CONFIG.lock().unwrap().vad_model = vad_model;
+    Ok(())
+}
+
+/// 获取已保存语音段的 WAV 音频，用于回放
+#[tauri::command]
+pub fn get_segment_audio(segment_id: String) -> Result<Vec<u8>, String> {
+    let archive_dir = CONFIG.lock().unwrap().archive_dir.clone();
+    read_archived_segment(archive_dir, &segment_id).map_err(|e| e.to_string())
+}
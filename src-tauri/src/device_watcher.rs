@@ -0,0 +1,70 @@
+use crate::global::{open_recorder, CONFIG, RECORDER};
+use crate::tray_icon::{all_input_devices, refresh_device_menu};
+use cpal::traits::DeviceTrait;
+use log::info;
+use std::thread;
+use std::time::Duration;
+use tauri::{menu::Submenu, AppHandle, Wry};
+
+/// 重新枚举输入设备的间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 启动输入设备监听线程
+/// 定期重新枚举输入设备：
+/// - 设备列表变化时刷新输入设备菜单
+/// - 当前设备被拔出或音频流出错时切换到默认输入设备
+/// - 选中的设备重新接入后切换回该设备
+pub(crate) fn watch_devices(app: AppHandle, device_menu: Submenu<Wry>) {
+    thread::spawn(move || {
+        let mut device_names = input_device_names();
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let current_device_names = input_device_names();
+            if current_device_names != device_names {
+                info!("输入设备列表变化: {:?}", current_device_names);
+                device_names = current_device_names;
+                refresh_device_menu(&app, &device_menu);
+            }
+            check_recorder(&device_names);
+        }
+    });
+}
+
+/// 当前所有输入设备的名称
+fn input_device_names() -> Vec<String> {
+    all_input_devices()
+        .iter()
+        .filter_map(|device| device.name().ok())
+        .collect()
+}
+
+/// 检查正在使用的输入设备，不可用或选中的设备重新接入时重新打开
+fn check_recorder(device_names: &[String]) {
+    // 先复制配置再获取 RECORDER 的锁，避免 RECORDER 初始化时重复获取 CONFIG 的锁
+    let config = CONFIG.lock().unwrap().clone();
+    let mut recorder = RECORDER.lock().unwrap();
+    let current_device_name = recorder.device_name().map(str::to_string);
+    let available = recorder.is_available()
+        && current_device_name
+            .as_ref()
+            .is_some_and(|name| device_names.contains(name));
+    // 正在使用备用设备，而选中的设备已重新接入
+    let select_device_returned = config.select_device_name.as_ref().is_some_and(|name| {
+        current_device_name.as_ref() != Some(name) && device_names.contains(name)
+    });
+    if available && !select_device_returned {
+        return;
+    }
+    // 没有任何输入设备时无需重复打开
+    if current_device_name.is_none() && device_names.is_empty() {
+        return;
+    }
+
+    let new_recorder = open_recorder(&config);
+    info!(
+        "切换输入设备: {} -> {}",
+        current_device_name.as_deref().unwrap_or("无"),
+        new_recorder.device_name().unwrap_or("无")
+    );
+    *recorder = new_recorder;
+}
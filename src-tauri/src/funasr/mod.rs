@@ -1,7 +1,15 @@
 pub mod models;
+mod transcribe;
 pub mod utils;
-pub use models::{pretreatment, Cache, Language, Paraformer, SenseVoice, Vad};
+pub use models::{
+    find_split_point, pretreatment, pretreatment_all, AudioEvent, Cache, CaptionMode, Emotion,
+    Language, LookbackFrames, Paraformer, SenseVoice, SileroVad, Vad, VadBackend, VadModel,
+    VoiceDetector,
+};
+pub use transcribe::{transcribe_file, TranscribedSegment};
 pub use utils::{
-    constant::{ENDPOINT_DETECTION_WAV_FRONTEND, SPEECH_RECOGNITION_WAV_FRONTEND},
-    default_device, devices, hosts, Frame, PointType, Recorder,
+    constant::SPEECH_RECOGNITION_WAV_FRONTEND, default_device, device_configs, devices, hosts,
+    input_channels, read_archived_segment, ArchiveMode, AudioArchive, AudioSource, ChannelMode,
+    CmvnConfig, DeviceConfigs, FileSource, Frame, OverflowPolicy, PcmFormat, PointType, Recorder,
+    Signal, SignalSource, StdinSource, StreamingFrontend, StreamingItn, VadConfig, VadSensitivity,
 };
@@ -0,0 +1,165 @@
+use crate::funasr::models::vad::VoiceDetector;
+use crate::funasr::utils::constant::{FBANK, SAMPLE_RATE};
+use crate::funasr::utils::{E2EVadModel, Frame, Segment, VadConfig};
+use anyhow::Result;
+use ndarray::Array2;
+use std::sync::Arc;
+
+/// 帧能量高出噪声基底该分贝数时，语音概率为 0.5
+const SPEECH_MARGIN_DB: f32 = 10.0;
+/// 语音概率随信噪比变化的平缓程度，单位：dB
+const MARGIN_SCALE_DB: f32 = 2.0;
+/// 过零率高于该值的帧接近白噪声，降低其语音概率
+const NOISE_ZERO_CROSSING_RATE: f32 = 0.5;
+/// 语音帧中噪声基底的更新速度相对静音帧的倍数，使噪声基底能跟上持续变大的环境噪声
+const SPEECH_FLOOR_RATE: f32 = 0.1;
+/// 噪声基底下降速度相对上升速度的倍数，环境噪声变小后较快跟上
+const FLOOR_FALL_RATE: f32 = 10.0;
+/// 噪声基底的下限，单位：dB，约为 16 位音频中幅度为 1～2 的噪声
+/// 避免数字静音（约 -100dB）把噪声基底拉到极低，之后的任何声音都被当作语音
+const MIN_NOISE_FLOOR_DB: f32 = 30.0;
+
+/// 基于帧能量与过零率的端点检测器，不需要加载模型
+/// 根据自适应的噪声基底估计每帧的语音概率，再由 `E2EVadModel` 的状态机确定语音段
+pub struct EnergyDetector {
+    /// 噪声基底，单位：dB
+    noise_floor: Option<f32>,
+    /// 更新噪声基底使用的帧数
+    noise_frames: f32,
+    /// 噪声基底的下限，不低于端点检测配置的分贝阈值
+    min_noise_floor: f32,
+    scorer: E2EVadModel,
+}
+
+impl EnergyDetector {
+    pub fn new(config: VadConfig) -> Self {
+        let frame_ms = FBANK.frame_shift() as f32 * 1000.0 / SAMPLE_RATE as f32;
+        Self {
+            noise_floor: None,
+            noise_frames: config.noise_frame_num_used_for_snr.max(1.0),
+            min_noise_floor: config.decibel_thres.max(MIN_NOISE_FLOOR_DB),
+            scorer: E2EVadModel::new(config, frame_ms),
+        }
+    }
+
+    /// 估计一帧为语音的概率，并更新噪声基底
+    fn speech_probability(&mut self, frame: &Frame) -> f32 {
+        let decibel = frame.decibel;
+        let noise_floor = self
+            .noise_floor
+            .get_or_insert(decibel.max(self.min_noise_floor));
+        let snr = decibel - *noise_floor;
+        let mut probability = 1.0 / (1.0 + (-(snr - SPEECH_MARGIN_DB) / MARGIN_SCALE_DB).exp());
+        if frame.zero_crossing_rate > NOISE_ZERO_CROSSING_RATE {
+            probability *= 0.5;
+        }
+
+        // 按帧数平滑更新，能量低于噪声基底时较快下降，单个安静的帧不会使噪声基底骤降
+        let target = decibel.max(self.min_noise_floor);
+        let rate = if target < *noise_floor {
+            FLOOR_FALL_RATE
+        } else if probability < 0.5 {
+            1.0
+        } else {
+            SPEECH_FLOOR_RATE
+        };
+        let rate = (rate / self.noise_frames).min(1.0);
+        *noise_floor += (target - *noise_floor) * rate;
+        probability
+    }
+}
+
+impl VoiceDetector for EnergyDetector {
+    fn detect(&mut self, _waveform: &[f32], frames: &[Arc<Frame>]) -> Result<Vec<Segment>> {
+        // 第一列为静音的概率，与 FSMN 模型的输出一致
+        let mut scores = Array2::zeros((frames.len(), 1));
+        let mut decibels = Vec::with_capacity(frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            scores[[i, 0]] = 1.0 - self.speech_probability(frame);
+            decibels.push(frame.decibel);
+        }
+        Ok(self.scorer.call(scores, &decibels))
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.scorer.elapsed_ms()
+    }
+
+    fn set_config(&mut self, config: VadConfig) {
+        self.noise_frames = config.noise_frame_num_used_for_snr.max(1.0);
+        self.min_noise_floor = config.decibel_thres.max(MIN_NOISE_FLOOR_DB);
+        self.scorer.set_config(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::funasr::models::{pretreatment, VadBackend};
+    use crate::funasr::utils::constant::{FBANK, SAMPLE_RATE};
+    use crate::funasr::utils::{
+        AudioSource, CmvnMode, FileSource, PointType, Signal, SignalSource,
+    };
+    use ndarray::Array1;
+    use std::time::Duration;
+
+    fn frame(decibel: f32) -> Frame {
+        Frame {
+            feature: Array1::zeros(0),
+            decibel,
+            zero_crossing_rate: 0.1,
+        }
+    }
+
+    #[test]
+    fn digital_silence_does_not_pin_noise_floor() {
+        let mut detector = EnergyDetector::new(VadConfig::default());
+        for _ in 0..100 {
+            detector.speech_probability(&frame(50.0));
+        }
+        // 单个数字静音帧只使噪声基底略微下降
+        detector.speech_probability(&frame(-100.0));
+        assert!(detector.noise_floor.unwrap() > 45.0);
+        assert!(detector.speech_probability(&frame(55.0)) < 0.5);
+
+        // 持续的数字静音使噪声基底降到下限为止
+        for _ in 0..1000 {
+            detector.speech_probability(&frame(-100.0));
+        }
+        assert!((detector.noise_floor.unwrap() - MIN_NOISE_FLOOR_DB).abs() < 0.01);
+        assert!(detector.speech_probability(&frame(35.0)) < 0.5);
+        assert!(detector.speech_probability(&frame(60.0)) > 0.5);
+    }
+
+    /// 使用合成的音频作为测试数据，端点检测不需要加载模型
+    #[test]
+    fn fixture_runs_through_endpoint_detection() {
+        let tone = Signal::Tone {
+            frequency: 300.0,
+            amplitude: 0.5,
+        };
+        let mut samples = vec![0.0; SAMPLE_RATE];
+        let mut tone = SignalSource::new(tone, Some(Duration::from_secs(2)));
+        while let Some(chunk) = tone.pop_head_sample(1600) {
+            samples.extend(chunk);
+        }
+        samples.extend(vec![0.0; SAMPLE_RATE * 2]);
+        let mut source = FileSource::from_samples(samples);
+
+        let mut detector = VadBackend::Energy
+            .detector(CmvnMode::Global, VadConfig::default())
+            .unwrap();
+        let mut remaining = Array1::zeros(0);
+        let mut segments = Vec::new();
+        while let Some(chunk) = source.pop_head_sample(1600) {
+            let (frames, rest) = pretreatment(&FBANK, &chunk, remaining);
+            remaining = rest;
+            segments.extend(detector.detect(&chunk, &frames).unwrap());
+        }
+        let types: Vec<PointType> = segments.iter().map(|s| s.segment_type).collect();
+        assert_eq!(types, [PointType::Start, PointType::End]);
+        assert!((600..=1000).contains(&segments[0].start));
+        let end = segments[1].end.unwrap();
+        assert!((3000..=3500).contains(&end), "语音段终点 {}ms", end);
+    }
+}
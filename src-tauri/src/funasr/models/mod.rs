@@ -1,9 +1,12 @@
+mod energy_vad;
 mod paraformer;
 mod sense_voice;
+mod silero_vad;
 mod utils;
 mod vad;
 
 pub use paraformer::{Cache, Paraformer};
-pub use sense_voice::{Language, SenseVoice};
-pub use utils::pretreatment;
-pub use vad::Vad;
+pub use sense_voice::{AudioEvent, CaptionMode, Emotion, Language, SenseVoice};
+pub use silero_vad::SileroVad;
+pub use utils::{find_split_point, pretreatment, pretreatment_all, LookbackFrames};
+pub use vad::{Vad, VadBackend, VadModel, VoiceDetector};
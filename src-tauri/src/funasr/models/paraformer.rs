@@ -1,5 +1,5 @@
-use crate::funasr::utils::constant::{MEL_BINS, SPEECH_RECOGNITION_LFR_M};
-use crate::funasr::utils::{read_token, OrtInferSession, TokenIdConverter};
+use crate::funasr::utils::constant::{FBANK, SPEECH_RECOGNITION_LFR_M};
+use crate::funasr::utils::{read_token, Detokenizer, OrtInferSession, TokenIdConverter};
 use anyhow::{anyhow, Ok, Result};
 use ndarray::{concatenate, s, Array1, Array2, Array3, ArrayView1, Axis};
 use ort::inputs;
@@ -21,18 +21,20 @@ pub struct Cache {
     cif_hidden: Array1<f32>,
     cif_alphas: f32,
     decoder_fsmn: Array3<f32>, // 16,512,10
+    detokenizer: Detokenizer,  // 在两次解码结果之间正确地添加空格
 }
 impl Default for Cache {
     fn default() -> Self {
         Self {
             feats: Array2::zeros((
                 CHUNK_SIZE_PRE + CHUNK_SIZE_BACK,
-                SPEECH_RECOGNITION_LFR_M * MEL_BINS,
+                SPEECH_RECOGNITION_LFR_M * FBANK.options().feature_dim(),
             )),
             start_idx: 0,
             cif_hidden: Array1::zeros(OUTPUT_SIZE),
             cif_alphas: 0f32,
             decoder_fsmn: Array3::zeros((FSMN_LAYER, OUTPUT_SIZE, FSMN_LORDER)),
+            detokenizer: Detokenizer::default(),
         }
     }
 }
@@ -163,7 +165,7 @@ impl Paraformer {
                 }
                 logits
             };
-            let result = self.decode(logits);
+            let result = self.decode(logits, &mut cache.detokenizer);
             return Ok(result);
         }
         Ok("".to_string())
@@ -262,7 +264,7 @@ impl Paraformer {
         Ok(result_frames)
     }
 
-    fn decode(&self, logits: Array2<f32>) -> String {
+    fn decode(&self, logits: Array2<f32>, detokenizer: &mut Detokenizer) -> String {
         // 获取每个时间步的最大概率索引 (argmax)
         let token_int: Vec<usize> = logits
             .outer_iter()
@@ -283,8 +285,7 @@ impl Paraformer {
 
         // 将整数ID转换为token
         let tokens = self.token_converter.ids2tokens(&filtered_token_int);
-        let tokens: String = tokens.join("");
-        tokens
+        detokenizer.push(&tokens)
     }
 }
 
@@ -1,4 +1,4 @@
-use crate::funasr::utils::{read_token, OrtInferSession, TokenIdConverter};
+use crate::funasr::utils::{detokenize, read_token, OrtInferSession, TokenIdConverter};
 use anyhow::{anyhow, Result};
 use ndarray::{Array2, Axis};
 use ort::inputs;
@@ -10,8 +10,10 @@ pub struct SenseVoice {
     session: OrtInferSession,
     token_converter: TokenIdConverter,
     pub language: Language,
+    /// 是否对识别结果进行逆文本正则化，将数字、日期、金额等转为阿拉伯数字
+    pub itn: bool,
 }
-#[derive(Clone, Copy, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Language {
     Auto = 0,
     Chinese = 3,
@@ -58,9 +60,166 @@ impl Language {
             _ => Err(anyhow!("无法识别的语言: {}", name)),
         }
     }
+
+    /// 由模型输出的语言标签解析语言，如 `<|zh|>`
+    fn from_token(token: &str) -> Option<Language> {
+        match token {
+            "<|zh|>" => Some(Language::Chinese),
+            "<|en|>" => Some(Language::English),
+            "<|yue|>" => Some(Language::Cantonese),
+            "<|ja|>" => Some(Language::Japanese),
+            "<|ko|>" => Some(Language::Korean),
+            "<|nospeech|>" => Some(Language::NoSpeech),
+            _ => None,
+        }
+    }
+}
+
+/// 说话人的情感
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Emotion {
+    Happy,
+    Sad,
+    Angry,
+    Neutral,
+    Fearful,
+    Disgusted,
+    Surprised,
+    Unknown,
+}
+
+impl Emotion {
+    /// 由模型输出的情感标签解析情感，如 `<|HAPPY|>`
+    fn from_token(token: &str) -> Option<Emotion> {
+        match token {
+            "<|HAPPY|>" => Some(Emotion::Happy),
+            "<|SAD|>" => Some(Emotion::Sad),
+            "<|ANGRY|>" => Some(Emotion::Angry),
+            "<|NEUTRAL|>" => Some(Emotion::Neutral),
+            "<|FEARFUL|>" => Some(Emotion::Fearful),
+            "<|DISGUSTED|>" => Some(Emotion::Disgusted),
+            "<|SURPRISED|>" => Some(Emotion::Surprised),
+            "<|EMO_UNKNOWN|>" => Some(Emotion::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// 语音段中的音频事件
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AudioEvent {
+    Speech,
+    Bgm,
+    Applause,
+    Laughter,
+    Cry,
+    Sneeze,
+    Breath,
+    Cough,
+    Unknown,
+}
+
+impl AudioEvent {
+    /// 由模型输出的事件标签解析音频事件，如 `<|Applause|>`
+    fn from_token(token: &str) -> Option<AudioEvent> {
+        match token {
+            "<|Speech|>" => Some(AudioEvent::Speech),
+            "<|BGM|>" => Some(AudioEvent::Bgm),
+            "<|Applause|>" => Some(AudioEvent::Applause),
+            "<|Laughter|>" => Some(AudioEvent::Laughter),
+            "<|Cry|>" => Some(AudioEvent::Cry),
+            "<|Sneeze|>" => Some(AudioEvent::Sneeze),
+            "<|Breath|>" => Some(AudioEvent::Breath),
+            "<|Cough|>" => Some(AudioEvent::Cough),
+            "<|Event_UNK|>" => Some(AudioEvent::Unknown),
+            _ => None,
+        }
+    }
+
+    /// 字幕中显示的事件名称，语音与未知事件不显示
+    /// 识别出的语言为英文时使用英文名称
+    fn label(&self, language: Option<Language>) -> Option<&'static str> {
+        let english = language == Some(Language::English);
+        let (chinese, english_label) = match self {
+            AudioEvent::Speech | AudioEvent::Unknown => return None,
+            AudioEvent::Bgm => ("音乐", "Music"),
+            AudioEvent::Applause => ("掌声", "Applause"),
+            AudioEvent::Laughter => ("笑声", "Laughter"),
+            AudioEvent::Cry => ("哭声", "Crying"),
+            AudioEvent::Sneeze => ("喷嚏", "Sneezing"),
+            AudioEvent::Breath => ("呼吸声", "Breathing"),
+            AudioEvent::Cough => ("咳嗽", "Coughing"),
+        };
+        Some(if english { english_label } else { chinese })
+    }
+}
+
+/// 字幕的显示方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CaptionMode {
+    /// 只显示识别的文字
+    #[default]
+    Plain,
+    /// 听障字幕，在文字前以 `[掌声]` 的形式显示音频事件
+    Sdh,
+}
+
+/// SenseVoice 的识别结果
+/// 模型在文字前输出语言、情感、音频事件与是否逆文本正则化的标签，
+/// 如 `<|zh|><|NEUTRAL|><|Speech|><|woitn|>`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SenseVoiceResult {
+    /// 识别出的语言
+    pub language: Option<Language>,
+    /// 说话人的情感
+    pub emotion: Option<Emotion>,
+    /// 音频事件
+    pub event: Option<AudioEvent>,
+    /// 文字是否经过逆文本正则化
+    pub itn: bool,
+    /// 去除标签后的文字
+    pub text: String,
+}
+
+impl SenseVoiceResult {
+    /// 由模型输出的 token 解析识别结果
+    fn from_tokens(tokens: Vec<String>) -> Self {
+        let mut result = SenseVoiceResult::default();
+        for token in &tokens {
+            if let Some(language) = Language::from_token(token) {
+                result.language = Some(language);
+            } else if let Some(emotion) = Emotion::from_token(token) {
+                result.emotion = Some(emotion);
+            } else if let Some(event) = AudioEvent::from_token(token) {
+                result.event = Some(event);
+            } else if token == "<|withitn|>" {
+                result.itn = true;
+            }
+        }
+        // 拼接时去除全部标签
+        result.text = detokenize(&tokens);
+        result
+    }
+
+    /// 按字幕显示方式生成字幕文字
+    pub fn caption(&self, mode: CaptionMode) -> String {
+        let label = match mode {
+            CaptionMode::Plain => None,
+            CaptionMode::Sdh => self.event.and_then(|event| event.label(self.language)),
+        };
+        match label {
+            Some(label) if self.text.is_empty() => format!("[{}]", label),
+            Some(label) => format!("[{}] {}", label, self.text),
+            None => self.text.clone(),
+        }
+    }
 }
 
 const BLANK_ID: usize = 0;
+/// 进行逆文本正则化的 textnorm 输入
+const WITH_ITN: i32 = 14;
+/// 不进行逆文本正则化的 textnorm 输入
+const WITHOUT_ITN: i32 = 15;
 
 impl SenseVoice {
     pub fn new(model_dir: Option<PathBuf>, language: Option<Language>) -> Result<Self> {
@@ -76,24 +235,30 @@ impl SenseVoice {
             session,
             token_converter,
             language,
+            itn: false,
         })
     }
     /// 参数
     /// - `features`:特征
-    pub fn call(&mut self, features: Array2<f32>) -> Result<String> {
+    pub fn call(&mut self, features: Array2<f32>) -> Result<SenseVoiceResult> {
+        // 音频帧不足一个 LFR 窗口时没有特征（如语音段切分后很短的结尾），不需要识别
+        if features.nrows() == 0 {
+            return Ok(SenseVoiceResult::default());
+        }
         self.inter(features)
     }
 
-    fn inter(&mut self, feats: Array2<f32>) -> Result<String> {
+    fn inter(&mut self, feats: Array2<f32>) -> Result<SenseVoiceResult> {
         let x_length = feats.shape()[0];
         let feats = feats.insert_axis(Axis(0));
         let language = self.language.clone() as i32;
+        let textnorm = if self.itn { WITH_ITN } else { WITHOUT_ITN };
         let logits = {
             let mut result = self.session.run(inputs![
               "speech"=>Tensor::from_array(feats)?,
               "speech_lengths"=>Tensor::from_array(([1], vec![x_length as i32]))?,
               "language"=>Tensor::from_array(([1], vec![language]))?,
-              "textnorm"=>Tensor::from_array(([1], vec![15i32]))?,
+              "textnorm"=>Tensor::from_array(([1], vec![textnorm]))?,
             ])?;
             let logits_tensor = result.remove("ctc_logits").unwrap();
             let logits = logits_tensor.try_extract_array()?;
@@ -102,38 +267,111 @@ impl SenseVoice {
                 .to_owned();
             logits
         };
-        let decoded_text = self.decode(logits)?;
-        Ok(decoded_text)
-    }
-
-    fn decode(&self, logits: Array2<f32>) -> Result<String> {
-        // 获取每个时间步长中概率最大的
-        let max_indices = logits
-            .axis_iter(Axis(0))
-            .map(|t| {
-                t.iter()
-                    .enumerate()
-                    .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-                    .map(|(i, _)| i as i32)
-                    .unwrap_or(0)
-            })
-            .collect::<Vec<_>>();
-        let mut merged_indices = Vec::new();
-        let mut current = max_indices[0];
-        for &index in &max_indices[1..] {
-            if index == current {
-                continue;
-            }
-            merged_indices.push(current);
-            current = index;
+        self.decode(logits)
+    }
+
+    fn decode(&self, logits: Array2<f32>) -> Result<SenseVoiceResult> {
+        let tokens: Vec<String> = self.token_converter.ids2tokens(&greedy_search(&logits));
+        Ok(SenseVoiceResult::from_tokens(tokens))
+    }
+}
+
+/// CTC 贪心解码，合并连续重复的结果并移除空白
+fn greedy_search(logits: &Array2<f32>) -> Vec<usize> {
+    // 获取每个时间步长中概率最大的
+    let mut merged_indices: Vec<usize> = logits
+        .axis_iter(Axis(0))
+        .map(|t| {
+            t.iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(i, _)| i)
+                .unwrap_or(0)
+        })
+        .collect();
+    // 合并连续重复的结果
+    merged_indices.dedup();
+    // 移除 BLANK_ID
+    merged_indices.retain(|&id| id != BLANK_ID);
+    merged_indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::funasr::models::pretreatment_all;
+    use crate::funasr::utils::constant::{FBANK, SPEECH_RECOGNITION_WAV_FRONTEND};
+
+    fn tokens(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|token| token.to_string()).collect()
+    }
+
+    #[test]
+    fn from_tokens_parses_tags() {
+        let result = SenseVoiceResult::from_tokens(tokens(&[
+            "<|zh|>",
+            "<|HAPPY|>",
+            "<|Applause|>",
+            "<|withitn|>",
+            "谢谢",
+            "大家",
+            "。",
+        ]));
+        assert_eq!(result.language, Some(Language::Chinese));
+        assert_eq!(result.emotion, Some(Emotion::Happy));
+        assert_eq!(result.event, Some(AudioEvent::Applause));
+        assert!(result.itn);
+        assert_eq!(result.text, "谢谢大家。");
+    }
+
+    #[test]
+    fn from_tokens_without_tags() {
+        let result = SenseVoiceResult::from_tokens(tokens(&["<|woitn|>", "▁hello", "▁world"]));
+        assert_eq!(result.language, None);
+        assert_eq!(result.emotion, None);
+        assert_eq!(result.event, None);
+        assert!(!result.itn);
+        assert_eq!(result.text, "hello world");
+    }
+
+    #[test]
+    fn caption_shows_events_only_in_sdh_mode() {
+        let result = SenseVoiceResult::from_tokens(tokens(&[
+            "<|zh|>",
+            "<|NEUTRAL|>",
+            "<|Laughter|>",
+            "<|woitn|>",
+            "好",
+        ]));
+        assert_eq!(result.caption(CaptionMode::Plain), "好");
+        assert_eq!(result.caption(CaptionMode::Sdh), "[笑声] 好");
+
+        // 英文使用英文名称，没有文字时只显示事件
+        let result = SenseVoiceResult::from_tokens(tokens(&["<|en|>", "<|BGM|>", "<|woitn|>"]));
+        assert_eq!(result.caption(CaptionMode::Plain), "");
+        assert_eq!(result.caption(CaptionMode::Sdh), "[Music]");
+
+        // 语音事件不显示
+        let result = SenseVoiceResult::from_tokens(tokens(&["<|zh|>", "<|Speech|>", "你好"]));
+        assert_eq!(result.caption(CaptionMode::Sdh), "你好");
+    }
+
+    #[test]
+    fn greedy_search_merges_repeats_and_blanks() {
+        let ids = [1, 1, BLANK_ID, 1, 2, 2, BLANK_ID];
+        let mut logits = Array2::zeros((ids.len(), 4));
+        for (t, &id) in ids.iter().enumerate() {
+            logits[[t, id]] = 1.0;
         }
-        merged_indices.push(current);
-        // 移除 BLANK_ID
-        merged_indices.retain(|&id| id != BLANK_ID as i32);
-        // 将 merged_indices 转换为文本
-        let merged_indices_usize: Vec<usize> = merged_indices.iter().map(|&x| x as usize).collect();
-        let tokens: Vec<String> = self.token_converter.ids2tokens(&merged_indices_usize);
-        let decoded_text = tokens.join("");
-        Ok(decoded_text)
+        assert_eq!(greedy_search(&logits), [1, 1, 2]);
+    }
+
+    #[test]
+    fn one_frame_tail_has_nothing_to_decode() {
+        let frames = pretreatment_all(&FBANK, &[0.1; 400]);
+        assert_eq!(frames.len(), 1);
+        let features = SPEECH_RECOGNITION_WAV_FRONTEND.extract_features(&frames);
+        assert_eq!(features.nrows(), 0);
+        assert!(greedy_search(&Array2::zeros((0, 4))).is_empty());
     }
 }
@@ -0,0 +1,121 @@
+use crate::funasr::models::vad::VoiceDetector;
+use crate::funasr::utils::constant::SAMPLE_RATE;
+use crate::funasr::utils::{E2EVadModel, Frame, OrtInferSession, Segment, VadConfig};
+use anyhow::{anyhow, Result};
+use ndarray::{concatenate, s, Array2, Array3, ArrayView1, Axis};
+use ort::inputs;
+use ort::value::Tensor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// 每次输入的音频采样数 32ms
+const WINDOW_SIZE: usize = 512;
+/// 拼接在窗口前的上一窗口末尾采样数
+const CONTEXT_SIZE: usize = 64;
+/// LSTM 状态的形状
+const STATE_SHAPE: (usize, usize, usize) = (2, 1, 128);
+
+/// Silero 语音端点检测模型（v5）
+/// 每次输入 512 个 16kHz 采样，输出该窗口为语音的概率
+pub struct SileroVad {
+    session: OrtInferSession,
+}
+
+impl SileroVad {
+    /// 创建 Silero VAD 实例
+    /// # Arguments
+    /// * `model_dir` - 模型文件目录，默认 "models"
+    pub fn new(model_dir: Option<PathBuf>) -> Result<Self> {
+        let model_dir = model_dir.unwrap_or_else(|| PathBuf::from("models"));
+        let model_path = model_dir.join("silero-vad.onnx");
+        if !model_path.exists() {
+            return Err(anyhow!("Model file not found: {}", model_path.display()));
+        }
+        let session = OrtInferSession::new(model_path)?;
+        Ok(Self { session })
+    }
+
+    /// 计算一个窗口为语音的概率
+    /// # 参数
+    /// - `input`: 上下文与窗口拼接后的音频 [1, CONTEXT_SIZE + WINDOW_SIZE]
+    /// - `state`: LSTM 状态，推理后更新
+    fn infer(&mut self, input: Array2<f32>, state: &mut Array3<f32>) -> Result<f32> {
+        let result = self.session.run(inputs![
+            "input" => Tensor::from_array(input)?,
+            "state" => Tensor::from_array(state.clone())?,
+            "sr" => Tensor::from_array(ndarray::arr0(SAMPLE_RATE as i64))?,
+        ])?;
+        let probability = result["output"].try_extract_array::<f32>()?;
+        let probability = probability
+            .iter()
+            .next()
+            .copied()
+            .ok_or_else(|| anyhow!("Silero VAD 输出为空"))?;
+        let new_state = result["stateN"].try_extract_array::<f32>()?;
+        *state = new_state.to_shape(STATE_SHAPE)?.to_owned();
+        Ok(probability)
+    }
+}
+
+/// Silero 端点检测器
+/// 保存 LSTM 状态、上一窗口的末尾采样以及不足一个窗口的音频，
+/// 语音概率由 `E2EVadModel` 的状态机确定语音段
+pub struct SileroDetector {
+    vad: Arc<Mutex<SileroVad>>,
+    /// LSTM 状态
+    state: Array3<f32>,
+    /// 上一窗口末尾的采样
+    context: Array2<f32>,
+    /// 不足一个窗口的音频
+    pending: Vec<f32>,
+    scorer: E2EVadModel,
+}
+
+impl SileroDetector {
+    pub fn new(vad: Arc<Mutex<SileroVad>>, config: VadConfig) -> Self {
+        let window_ms = WINDOW_SIZE as f32 * 1000.0 / SAMPLE_RATE as f32;
+        Self {
+            vad,
+            state: Array3::zeros(STATE_SHAPE),
+            context: Array2::zeros((1, CONTEXT_SIZE)),
+            pending: Vec::new(),
+            scorer: E2EVadModel::new(config, window_ms),
+        }
+    }
+}
+
+impl VoiceDetector for SileroDetector {
+    fn detect(&mut self, waveform: &[f32], _frames: &[Arc<Frame>]) -> Result<Vec<Segment>> {
+        self.pending.extend_from_slice(waveform);
+        let windows = self.pending.len() / WINDOW_SIZE;
+        // 第一列为静音的概率，与 FSMN 模型的输出一致
+        let mut scores = Array2::zeros((windows, 1));
+        let mut decibels = Vec::with_capacity(windows);
+        {
+            let mut vad = self.vad.lock().unwrap();
+            for (i, window) in self.pending.chunks_exact(WINDOW_SIZE).enumerate() {
+                let window = ArrayView1::from(window).insert_axis(Axis(0));
+                let input = concatenate![Axis(1), self.context, window];
+                self.context = input.slice(s![.., WINDOW_SIZE..]).to_owned();
+                scores[[i, 0]] = 1.0 - vad.infer(input, &mut self.state)?;
+                decibels.push(compute_decibel(window));
+            }
+        }
+        self.pending.drain(..windows * WINDOW_SIZE);
+        Ok(self.scorer.call(scores, &decibels))
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.scorer.elapsed_ms()
+    }
+
+    fn set_config(&mut self, config: VadConfig) {
+        self.scorer.set_config(config);
+    }
+}
+
+/// 计算窗口的分贝值，与音频帧一样按 [-32768,32768] 范围计算
+fn compute_decibel(window: ndarray::ArrayView2<f32>) -> f32 {
+    let energy: f32 = window.iter().map(|x| (x * 32768.0).powi(2)).sum();
+    10.0 * (energy + 1e-10).log10()
+}
@@ -1,10 +1,12 @@
-use crate::funasr::utils::{fbank, Frame};
-use ndarray::{concatenate, Array1, Axis};
+use crate::funasr::utils::{Fbank, Frame, VadConfig};
+use ndarray::Array1;
+use std::sync::Arc;
 
 /// 预处理音频数据
 /// 将音频数据转换为 [-32768,32768] 范围，并与剩余音频数据拼接
-/// 返回特征和剩余音频数据
+/// 返回特征和剩余音频数据，音频帧以 `Arc` 在各个识别流程间共享
 /// # 参数
+/// - `fbank`: Filter Bank 特征提取器
 /// - `waveform`: 当前音频数据
 /// - `reserve_waveforms`: 上一次处理剩余的音频数据
 /// - `noise`: 是否添加噪声
@@ -13,16 +15,102 @@ use ndarray::{concatenate, Array1, Axis};
 /// - `waveform`: 特征对应的音频数据
 /// - `remaining_waveform`: 剩余的音频数据
 pub fn pretreatment(
-    waveform: Vec<f32>,
+    fbank: &Fbank,
+    waveform: &[f32],
     reserve_waveforms: Array1<f32>,
-) -> (Vec<Frame>, Array1<f32>) {
-    let mut waveform = Array1::from_vec(waveform);
+) -> (Vec<Arc<Frame>>, Array1<f32>) {
+    // 没有剩余的音频数据时为音频的开头
+    let is_start = reserve_waveforms.is_empty();
+    // 将上次剩余的音频数据与当前音频数据拼接，并将当前音频数据转换为 [-32768,32768]
+    let mut samples = Vec::with_capacity(reserve_waveforms.len() + waveform.len());
+    samples.extend(reserve_waveforms.iter());
+    samples.extend(waveform.iter().map(|x| x * 32768.0f32));
+    let (frames, remaining_waveform) = fbank.compute(Array1::from_vec(samples), is_start);
+    (
+        frames.into_iter().map(Arc::new).collect(),
+        remaining_waveform,
+    )
+}
+
+/// 预处理完整的音频数据，多线程提取全部音频帧，用于文件识别
+/// # 参数
+/// - `fbank`: Filter Bank 特征提取器
+/// - `waveform`: [-1,1] 范围的完整音频数据
+pub fn pretreatment_all(fbank: &Fbank, waveform: &[f32]) -> Vec<Arc<Frame>> {
     // 将音频数据转换为 [-32768,32768]
-    waveform.mapv_inplace(|x| x * 32768.0f32);
-    // 将上次剩余的音频数据与当前音频数据拼接
-    waveform = concatenate![Axis(0), reserve_waveforms, waveform];
-    let (frames, remaining_waveform) = fbank(waveform);
-    (frames, remaining_waveform)
+    let waveform = Array1::from_iter(waveform.iter().map(|x| x * 32768.0f32));
+    let (mut frames, remaining_waveform) = fbank.compute_parallel(waveform, true);
+    frames.extend(fbank.finish(remaining_waveform));
+    frames.into_iter().map(Arc::new).collect()
+}
+/// 查找过长语音段的切分点
+/// 在末尾 `window` 个音频帧中选择分贝值最低的帧，切分点位于该帧之后
+/// # 返回值
+/// - 切分后前一段的音频帧数
+pub fn find_split_point(frames: &[Arc<Frame>], window: usize) -> usize {
+    let start = frames.len().saturating_sub(window);
+    frames[start..]
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.decibel.total_cmp(&b.decibel))
+        .map_or(frames.len(), |(i, _)| start + i + 1)
+}
+
+/// 语音起点之前的音频帧缓存
+/// 端点检测确认语音起点时，起点已在检测到的帧之前 `VadConfig::start_lookback_ms` 处，
+/// 缓存足够的音频帧使语音段从起点开始
+pub struct LookbackFrames {
+    frames: Vec<Arc<Frame>>,
+    /// 最新一块音频之前保留的帧数
+    capacity: usize,
+    /// 已缓存的音频帧总数
+    frame_count: usize,
+    /// 每帧的时长，单位：ms
+    frame_shift_ms: f32,
+}
+
+impl LookbackFrames {
+    /// # 参数
+    /// - `config`: 端点检测配置，决定保留的帧数
+    /// - `frame_shift_ms`: fbank 的帧移
+    pub fn new(config: &VadConfig, frame_shift_ms: f32) -> Self {
+        let mut lookback = Self {
+            frames: Vec::new(),
+            capacity: 0,
+            frame_count: 0,
+            frame_shift_ms,
+        };
+        lookback.set_config(config);
+        lookback
+    }
+
+    /// 按新的端点检测配置调整保留的帧数
+    pub fn set_config(&mut self, config: &VadConfig) {
+        self.capacity = (config.start_lookback_ms() as f32 / self.frame_shift_ms).ceil() as usize;
+    }
+
+    /// 缓存最新一块音频的帧，语音起点可能在这块音频的任意位置检测到，因此这块音频全部保留
+    pub fn push(&mut self, frames: &[Arc<Frame>]) {
+        if self.frames.len() > self.capacity {
+            self.frames.drain(0..self.frames.len() - self.capacity);
+        }
+        self.frames.extend(frames.iter().cloned());
+        self.frame_count += frames.len();
+    }
+
+    /// 取出从语音起点开始的音频帧，并清空缓存
+    /// # 参数
+    /// - `start_ms`: 语音起点，单位：ms，从第一帧开始计算
+    /// # 返回值
+    /// - 第一个取出的帧的序号，以及取出的音频帧
+    pub fn take_from(&mut self, start_ms: u64) -> (usize, Vec<Arc<Frame>>) {
+        let first = self.frame_count - self.frames.len();
+        let start =
+            ((start_ms as f32 / self.frame_shift_ms) as usize).clamp(first, self.frame_count);
+        let mut frames = std::mem::take(&mut self.frames);
+        frames.drain(0..start - first);
+        (start, frames)
+    }
 }
 // /// 预处理音频数据 在音频数据尾端填充空白 以保证所有数据都被处理
 // ///
@@ -38,3 +126,40 @@ pub fn pretreatment(
 //     let (frames, _) = fbank(waveform);
 //     frames
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(count: usize) -> Vec<Arc<Frame>> {
+        (0..count)
+            .map(|i| {
+                Arc::new(Frame {
+                    feature: Array1::zeros(0),
+                    decibel: i as f32,
+                    zero_crossing_rate: 0.0,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lookback_keeps_frames_back_to_start_point() {
+        // 默认配置的语音起点最多向前扩展 400ms，即 40 帧
+        let config = VadConfig::default();
+        let mut lookback = LookbackFrames::new(&config, 10.0);
+        for _ in 0..10 {
+            lookback.push(&frames(10));
+        }
+        // 在最新一块音频的第一帧确认语音，起点为该帧之前 40 帧
+        let (start, taken) = lookback.take_from(500);
+        assert_eq!(start, 50);
+        assert_eq!(taken.len(), 50);
+
+        // 缓存不足时从最早的帧开始
+        lookback.push(&frames(10));
+        let (start, taken) = lookback.take_from(0);
+        assert_eq!(start, 100);
+        assert_eq!(taken.len(), 10);
+    }
+}
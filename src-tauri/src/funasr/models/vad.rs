@@ -1,21 +1,151 @@
-use crate::funasr::utils::{E2EVadModel, Frame, OrtInferSession, Segment};
+use crate::funasr::models::energy_vad::EnergyDetector;
+use crate::funasr::models::silero_vad::{SileroDetector, SileroVad};
+use crate::funasr::utils::constant::{ENDPOINT_DETECTION_WAV_FRONTEND, FBANK};
+use crate::funasr::utils::{
+    CmvnMode, E2EVadModel, Frame, OrtInferSession, Segment, StreamingFrontend, VadConfig,
+};
 use anyhow::{anyhow, Ok, Result};
 use ndarray::{Array2, Axis};
 use ort::inputs;
 use ort::value::{Tensor, Value};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::usize;
 const FSMN_LAYERS: usize = 4;
 const PROJ_DIM: usize = 128;
 const LORDER: usize = 20;
 
+/// 语音端点检测模型的种类
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum VadModel {
+    /// FunASR 的 FSMN 模型 `vad.onnx`，输入 fbank 特征
+    #[default]
+    Fsmn,
+    /// Silero 模型 `silero-vad.onnx`，直接输入音频，对音乐与键盘声的误检较少
+    Silero,
+    /// 基于帧能量与过零率的检测，不需要模型，神经网络模型加载失败时使用
+    Energy,
+}
+
+/// 语音端点检测器，每路音频流使用独立的检测器
+pub trait VoiceDetector: Send {
+    /// 检测语音端点
+    /// # 参数
+    /// - `waveform`: 16kHz [-1,1] 范围的音频数据
+    /// - `frames`: 由 `waveform` 提取的音频帧
+    fn detect(&mut self, waveform: &[f32], frames: &[Arc<Frame>]) -> Result<Vec<Segment>>;
+
+    /// 会话开始以来已检测的音频时长，单位：ms
+    fn elapsed_ms(&self) -> u64;
+
+    /// 修改端点检测配置，立即生效
+    fn set_config(&mut self, config: VadConfig);
+}
+
+/// 已加载的语音端点检测模型，多路音频流共用
+#[derive(Clone)]
+pub enum VadBackend {
+    Fsmn(Arc<Mutex<Vad>>),
+    Silero(Arc<Mutex<SileroVad>>),
+    Energy,
+}
+
+impl VadBackend {
+    /// 为一路音频流创建端点检测器
+    /// # 参数
+    /// - `cmvn_mode`: FSMN 模型前端的 CMVN 方式
+    /// - `config`: 端点检测配置
+    pub fn detector(
+        &self,
+        cmvn_mode: CmvnMode,
+        config: VadConfig,
+    ) -> Result<Box<dyn VoiceDetector>> {
+        Ok(match self {
+            VadBackend::Fsmn(vad) => Box::new(FsmnDetector::new(vad.clone(), cmvn_mode, config)?),
+            VadBackend::Silero(vad) => Box::new(SileroDetector::new(vad.clone(), config)),
+            VadBackend::Energy => Box::new(EnergyDetector::new(config)),
+        })
+    }
+}
+
 pub struct Vad {
     session: OrtInferSession,
-    scorer: E2EVadModel,
+}
 
+/// FSMN 端点检测器，将音频帧转为特征后检测
+pub struct FsmnDetector {
+    vad: Arc<Mutex<Vad>>,
+    frontend: StreamingFrontend,
+    cache: VadCache,
+}
+
+impl FsmnDetector {
+    pub fn new(vad: Arc<Mutex<Vad>>, cmvn_mode: CmvnMode, config: VadConfig) -> Result<Self> {
+        Ok(Self {
+            vad,
+            frontend: StreamingFrontend::new(&ENDPOINT_DETECTION_WAV_FRONTEND, cmvn_mode),
+            cache: VadCache::new(config)?,
+        })
+    }
+}
+
+impl VoiceDetector for FsmnDetector {
+    fn detect(&mut self, _waveform: &[f32], frames: &[Arc<Frame>]) -> Result<Vec<Segment>> {
+        let (features, feature_frames) = self.frontend.accept(frames);
+        self.vad
+            .lock()
+            .unwrap()
+            .call(features, &feature_frames, &mut self.cache)
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.cache.elapsed_ms()
+    }
+
+    fn set_config(&mut self, config: VadConfig) {
+        self.cache.set_config(config);
+    }
+}
+
+/// 语音端点检测的流式状态
+/// 每路音频流需要使用独立的缓存
+pub struct VadCache {
+    scorer: E2EVadModel,
     in_cache: Vec<Value>,
 }
 
+impl VadCache {
+    /// # 参数
+    /// - `config`: 端点检测配置
+    pub fn new(config: VadConfig) -> Result<Self> {
+        let mut in_cache = Vec::with_capacity(FSMN_LAYERS);
+        for _ in 0..FSMN_LAYERS {
+            in_cache.push(
+                Tensor::from_array(ndarray::Array4::<f32>::zeros((1, PROJ_DIM, LORDER - 1, 1)))?
+                    .into(),
+            );
+        }
+        Ok(Self {
+            scorer: E2EVadModel::new(
+                config,
+                FBANK.options().frame_shift_ms * ENDPOINT_DETECTION_WAV_FRONTEND.lfr_n() as f32,
+            ),
+            in_cache,
+        })
+    }
+
+    /// 会话开始以来已检测的音频时长，单位：ms
+    pub fn elapsed_ms(&self) -> u64 {
+        self.scorer.elapsed_ms()
+    }
+
+    /// 修改端点检测配置，立即生效
+    pub fn set_config(&mut self, config: VadConfig) {
+        self.scorer.set_config(config);
+    }
+}
+
 impl Vad {
     /// 创建 VAD 实例
     /// # Arguments
@@ -26,34 +156,41 @@ impl Vad {
         if !model_path.exists() {
             return Err(anyhow!("Model file not found: {}", model_path.display()));
         }
-        let session = OrtInferSession::new(model_path)?;
-
-        let mut in_cache = Vec::with_capacity(FSMN_LAYERS);
-        for _ in 0..FSMN_LAYERS {
-            in_cache.push(
-                Tensor::from_array(ndarray::Array4::<f32>::zeros((1, PROJ_DIM, LORDER - 1, 1)))?
-                    .into(),
-            );
+        // 端点检测与语音识别共用 fbank 特征，配置不一致时特征与模型不匹配
+        if ENDPOINT_DETECTION_WAV_FRONTEND.fbank_options() != FBANK.options() {
+            return Err(anyhow!(
+                "端点检测模型与语音识别模型的 fbank 配置不一致: {:?} != {:?}",
+                ENDPOINT_DETECTION_WAV_FRONTEND.fbank_options(),
+                FBANK.options()
+            ));
         }
-        Ok(Self {
-            session,
-            scorer: E2EVadModel::default(),
-            in_cache,
-        })
+        let session = OrtInferSession::new(model_path)?;
+        Ok(Self { session })
     }
 
-    pub fn call(&mut self, features: Array2<f32>, frames: &Vec<Frame>) -> Result<Vec<Segment>> {
-        let scores = self.infer(features)?;
-        Ok(self.scorer.call(scores, frames))
+    /// 检测语音端点
+    /// # 参数
+    /// - `features`: 特征
+    /// - `frames`: 每行特征对应的音频帧
+    /// - `cache`: 缓存
+    pub fn call(
+        &mut self,
+        features: Array2<f32>,
+        frames: &[Arc<Frame>],
+        cache: &mut VadCache,
+    ) -> Result<Vec<Segment>> {
+        let scores = self.infer(features, cache)?;
+        let decibels: Vec<f32> = frames.iter().map(|frame| frame.decibel).collect();
+        Ok(cache.scorer.call(scores, &decibels))
     }
 
-    fn infer(&mut self, features: Array2<f32>) -> Result<Array2<f32>> {
+    fn infer(&mut self, features: Array2<f32>, cache: &mut VadCache) -> Result<Array2<f32>> {
         // 构造输入张量
         let mut inputs = inputs![
             "speech"=>Tensor::from_array(features.insert_axis(Axis(0)))?,
         ];
-        for (i, cache) in self.in_cache.iter().enumerate() {
-            inputs.push((format!("in_cache{}", i).into(), cache.into()));
+        for (i, in_cache) in cache.in_cache.iter().enumerate() {
+            inputs.push((format!("in_cache{}", i).into(), in_cache.into()));
         }
         let mut result = self.session.run(inputs)?;
         let scores = Self::extract_scores(&result[0])?;
@@ -65,7 +202,7 @@ impl Vad {
                     .expect("语音端点检测提取缓存失败"),
             );
         }
-        self.in_cache = new_caches;
+        cache.in_cache = new_caches;
         Ok(scores)
     }
 
@@ -0,0 +1,202 @@
+use crate::funasr::utils::constant::{FBANK, MAX_SENSE_VOICE_FRAMES, SPLIT_LOOKBACK_FRAMES};
+use crate::funasr::utils::read_audio_file_channels;
+use crate::funasr::{
+    find_split_point, pretreatment_all, AudioEvent, CaptionMode, ChannelMode, CmvnConfig, Emotion,
+    Frame, Language, LookbackFrames, PointType, SenseVoice, StreamingFrontend, VadBackend,
+    VadConfig, SPEECH_RECOGNITION_WAV_FRONTEND,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::mem::take;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// 每次送入 VAD 的音频帧数 100ms
+const CHUNK_FRAMES: usize = 10;
+
+/// 离线识别得到的语音段
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TranscribedSegment {
+    /// 声道序号，从 0 开始
+    pub channel: usize,
+    /// 起始时间，单位：ms
+    pub start: u64,
+    /// 结束时间，单位：ms
+    pub end: u64,
+    /// 识别结果，按字幕显示方式生成
+    pub text: String,
+    /// 识别出的语言
+    pub language: Option<Language>,
+    /// 说话人的情感
+    pub emotion: Option<Emotion>,
+    /// 音频事件
+    pub event: Option<AudioEvent>,
+    /// 文字是否经过逆文本正则化
+    pub itn: bool,
+}
+
+/// 离线识别音频文件
+/// # 参数
+/// - `path`: 音频文件路径
+/// - `channel_mode`: 多声道音频的处理方式，`ChannelMode::PerChannel` 时每个声道单独识别
+/// - `cmvn`: VAD 与语音识别前端的 CMVN 方式，每个声道单独统计
+/// - `vad_config`: 端点检测配置
+/// - `vad`: 语音端点检测模型，每个声道创建单独的检测器，仅在检测时持有锁
+/// - `sense_voice`: 语音识别模型，仅在识别单个语音段时持有锁
+/// - `caption_mode`: 字幕的显示方式
+/// # 返回值
+/// - 按起始时间排序的语音段
+pub fn transcribe_file(
+    path: impl AsRef<Path>,
+    channel_mode: ChannelMode,
+    cmvn: CmvnConfig,
+    vad_config: &VadConfig,
+    vad: &VadBackend,
+    sense_voice: &Mutex<SenseVoice>,
+    caption_mode: CaptionMode,
+) -> Result<Vec<TranscribedSegment>> {
+    let mut segments: Vec<TranscribedSegment> = Vec::new();
+    for (channel, waveform) in read_audio_file_channels(path, channel_mode)?
+        .into_iter()
+        .enumerate()
+    {
+        segments.extend(transcribe_waveform(
+            waveform,
+            channel,
+            cmvn,
+            vad_config,
+            vad,
+            sense_voice,
+            caption_mode,
+        )?);
+    }
+    segments.sort_by_key(|segment| (segment.start, segment.channel));
+    Ok(segments)
+}
+
+/// 离线识别单声道音频数据
+/// # 参数
+/// - `waveform`: 16kHz 单声道 [-1,1] 范围的音频数据
+/// - `channel`: 音频数据所属的声道序号
+/// - `cmvn`: VAD 与语音识别前端的 CMVN 方式
+/// - `vad_config`: 端点检测配置
+pub fn transcribe_waveform(
+    waveform: Vec<f32>,
+    channel: usize,
+    cmvn: CmvnConfig,
+    vad_config: &VadConfig,
+    vad: &VadBackend,
+    sense_voice: &Mutex<SenseVoice>,
+    caption_mode: CaptionMode,
+) -> Result<Vec<TranscribedSegment>> {
+    let mut segments: Vec<TranscribedSegment> = Vec::new();
+    let mut detector = vad.detector(cmvn.vad, vad_config.clone())?;
+
+    let mut recorder_waveform: bool = false; // 是否处于语音段中
+    let mut frame_count: usize = 0; // 已提取的音频帧数
+    let mut segment_start: usize = 0; // 当前语音段起始帧
+    let mut lookback = LookbackFrames::new(vad_config, FBANK.options().frame_shift_ms);
+    let mut recognition_frontend =
+        StreamingFrontend::new(&SPEECH_RECOGNITION_WAV_FRONTEND, cmvn.recognition);
+    let mut sense_voice_frames: Vec<Arc<Frame>> = Vec::new();
+
+    // 文件的音频已全部读取，一次性多线程提取全部音频帧
+    let all_frames = pretreatment_all(&FBANK, &waveform);
+    let chunk_samples = CHUNK_FRAMES * FBANK.frame_shift();
+    for (index, frames) in all_frames.chunks(CHUNK_FRAMES).enumerate() {
+        frame_count += frames.len();
+        // 与音频帧对应的原始音频，供直接处理音频的检测模型使用
+        let chunk_start = (index * chunk_samples).min(waveform.len());
+        let chunk_end = ((index + 1) * chunk_samples).min(waveform.len());
+        let vad_segments = detector.detect(&waveform[chunk_start..chunk_end], frames)?;
+
+        if recorder_waveform {
+            sense_voice_frames.extend(frames.iter().cloned());
+        }
+
+        lookback.push(frames);
+
+        for segment in vad_segments {
+            match segment.segment_type {
+                PointType::Start => {
+                    recorder_waveform = true;
+                    (segment_start, sense_voice_frames) = lookback.take_from(segment.start);
+                }
+                PointType::End => {
+                    recorder_waveform = false;
+                    let frames = take(&mut sense_voice_frames);
+                    // 与实时识别一致，使用端点检测给出的终点
+                    let end_frame = segment.end.map_or(frame_count, |end| {
+                        (end as f32 / FBANK.options().frame_shift_ms) as usize
+                    });
+                    segments.push(recognize(
+                        &frames,
+                        &mut recognition_frontend,
+                        channel,
+                        segment_start,
+                        end_frame,
+                        sense_voice,
+                        caption_mode,
+                    )?);
+                }
+            }
+        }
+        // 语音段过长时在末尾能量最低的帧处切分，之后的音频帧作为新语音段的开头
+        if recorder_waveform && sense_voice_frames.len() >= MAX_SENSE_VOICE_FRAMES {
+            let split = find_split_point(&sense_voice_frames, SPLIT_LOOKBACK_FRAMES);
+            let tail = sense_voice_frames.split_off(split);
+            let split_frame = frame_count - tail.len();
+            segments.push(recognize(
+                &sense_voice_frames,
+                &mut recognition_frontend,
+                channel,
+                segment_start,
+                split_frame,
+                sense_voice,
+                caption_mode,
+            )?);
+            segment_start = split_frame;
+            sense_voice_frames = tail;
+        }
+    }
+    // 文件结束时仍处于语音段中，则识别剩余部分
+    if recorder_waveform && !sense_voice_frames.is_empty() {
+        segments.push(recognize(
+            &sense_voice_frames,
+            &mut recognition_frontend,
+            channel,
+            segment_start,
+            frame_count,
+            sense_voice,
+            caption_mode,
+        )?);
+    }
+    Ok(segments)
+}
+
+/// 识别单个语音段
+/// 使用在线 CMVN 时先以该语音段更新统计量
+fn recognize(
+    frames: &[Arc<Frame>],
+    frontend: &mut StreamingFrontend,
+    channel: usize,
+    start_frame: usize,
+    end_frame: usize,
+    sense_voice: &Mutex<SenseVoice>,
+    caption_mode: CaptionMode,
+) -> Result<TranscribedSegment> {
+    frontend.observe(frames);
+    let features = frontend.extract_features(frames);
+    let result = sense_voice.lock().unwrap().call(features)?;
+    let frame_shift_ms = FBANK.options().frame_shift_ms;
+    Ok(TranscribedSegment {
+        channel,
+        start: (start_frame as f32 * frame_shift_ms) as u64,
+        end: (end_frame as f32 * frame_shift_ms) as u64,
+        text: result.caption(caption_mode),
+        language: result.language,
+        emotion: result.emotion,
+        event: result.event,
+        itn: result.itn,
+    })
+}
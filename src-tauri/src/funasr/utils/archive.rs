@@ -0,0 +1,358 @@
+use crate::funasr::utils::constant::SAMPLE_RATE;
+use anyhow::{anyhow, Result};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Cursor};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 语音段索引文件名
+const INDEX_FILE_NAME: &str = "segments.json";
+/// 按语音段保存时缓存的最近音频长度 2s，用于写入语音起点之前的音频
+const RECENT_SAMPLES: usize = SAMPLE_RATE * 2;
+
+const WAV_SPEC: WavSpec = WavSpec {
+    channels: 1,
+    sample_rate: SAMPLE_RATE as u32,
+    bits_per_sample: 16,
+    sample_format: SampleFormat::Int,
+};
+
+/// 识别音频的保存方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ArchiveMode {
+    /// 不保存
+    #[default]
+    Disabled,
+    /// 每个声道的全部音频保存为一个文件
+    Continuous,
+    /// 每个语音段保存为一个文件
+    PerSegment,
+}
+
+/// 已保存的语音段，保存在会话目录的 `segments.json` 中
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArchivedSegment {
+    /// 语音段 id，格式为 `{会话}-{序号}`
+    pub id: String,
+    /// 声道序号，从 0 开始
+    pub channel: usize,
+    /// 音频文件名，位于会话目录中
+    pub file: String,
+    /// 语音段在音频文件中的起始位置，单位：ms
+    pub offset: u64,
+    /// 相对会话开始的起始时间，单位：ms
+    pub start: u64,
+    /// 相对会话开始的结束时间，单位：ms
+    pub end: u64,
+    /// 识别结果
+    pub text: String,
+}
+
+/// 单个声道的保存状态
+#[derive(Default)]
+struct ChannelArchive {
+    /// 已写入的采样数
+    written: u64,
+    /// 最近的音频
+    recent: VecDeque<f32>,
+    /// 全部音频的文件
+    continuous: Option<WavWriter<BufWriter<File>>>,
+    /// 当前语音段
+    segment: Option<ActiveSegment>,
+}
+
+/// 正在进行的语音段
+struct ActiveSegment {
+    id: String,
+    /// 起始位置，单位：采样
+    start: u64,
+    /// 按语音段保存时的音频文件
+    writer: Option<WavWriter<BufWriter<File>>>,
+}
+
+/// 识别音频存档
+/// 每次识别创建一个会话目录，音频以 16kHz 16位单声道 WAV 格式保存，
+/// 语音段与音频文件的对应关系保存在 `segments.json` 中
+pub struct AudioArchive {
+    mode: ArchiveMode,
+    /// 会话名称，为会话开始时的毫秒时间戳
+    session: String,
+    /// 会话目录
+    dir: PathBuf,
+    channels: Vec<ChannelArchive>,
+    segments: Vec<ArchivedSegment>,
+    /// 下一个语音段的序号
+    next_segment: usize,
+}
+
+impl AudioArchive {
+    /// 在 `dir` 下创建新的会话目录
+    pub fn new(dir: impl AsRef<Path>, mode: ArchiveMode) -> Result<Self> {
+        let session = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_millis()
+            .to_string();
+        let dir = dir.as_ref().join(&session);
+        create_dir_all(&dir)?;
+        Ok(Self {
+            mode,
+            session,
+            dir,
+            channels: Vec::new(),
+            segments: Vec::new(),
+            next_segment: 0,
+        })
+    }
+
+    /// 写入声道的音频数据
+    pub fn write(&mut self, channel: usize, samples: &[f32]) -> Result<()> {
+        let mode = self.mode;
+        let file_path = self.dir.join(channel_file_name(channel));
+        let archive = self.channel(channel);
+        archive.written += samples.len() as u64;
+        if mode == ArchiveMode::Continuous {
+            if archive.continuous.is_none() {
+                archive.continuous = Some(WavWriter::create(file_path, WAV_SPEC)?);
+            }
+            if let Some(writer) = archive.continuous.as_mut() {
+                write_samples(writer, samples)?;
+            }
+            return Ok(());
+        }
+        if let Some(ActiveSegment {
+            writer: Some(writer),
+            ..
+        }) = archive.segment.as_mut()
+        {
+            write_samples(writer, samples)?;
+        }
+        archive.recent.extend(samples);
+        if archive.recent.len() > RECENT_SAMPLES {
+            archive
+                .recent
+                .drain(..archive.recent.len() - RECENT_SAMPLES);
+        }
+        Ok(())
+    }
+
+    /// 开始一个语音段
+    /// # 参数
+    /// - `channel`: 声道序号
+    /// - `lookback`: 语音起点距离已写入音频末尾的采样数
+    /// # 返回值
+    /// - 语音段 id
+    pub fn start_segment(&mut self, channel: usize, lookback: usize) -> Result<String> {
+        let id = format!("{}-{}", self.session, self.next_segment);
+        self.next_segment += 1;
+        let mode = self.mode;
+        let file_path = self.dir.join(segment_file_name(&id));
+        let archive = self.channel(channel);
+        let mut lookback = lookback.min(archive.written as usize);
+        if mode == ArchiveMode::PerSegment {
+            // 只缓存了最近的音频，起点不能早于缓存的开头
+            lookback = lookback.min(archive.recent.len());
+        }
+        let start = archive.written - lookback as u64;
+        let writer = if mode == ArchiveMode::PerSegment {
+            let mut writer = WavWriter::create(file_path, WAV_SPEC)?;
+            let recent: Vec<f32> = archive
+                .recent
+                .range(archive.recent.len() - lookback..)
+                .copied()
+                .collect();
+            write_samples(&mut writer, &recent)?;
+            Some(writer)
+        } else {
+            None
+        };
+        archive.segment = Some(ActiveSegment {
+            id: id.clone(),
+            start,
+            writer,
+        });
+        Ok(id)
+    }
+
+    /// 结束当前语音段，并更新索引文件
+    /// # 参数
+    /// - `channel`: 声道序号
+    /// - `lookback`: 语音终点距离已写入音频末尾的采样数
+    /// - `text`: 语音段的识别结果
+    pub fn end_segment(
+        &mut self,
+        channel: usize,
+        lookback: usize,
+        text: &str,
+    ) -> Result<Option<ArchivedSegment>> {
+        let archive = self.channel(channel);
+        let Some(ActiveSegment { id, start, writer }) = archive.segment.take() else {
+            return Ok(None);
+        };
+        let end = archive.written.saturating_sub(lookback as u64).max(start);
+        let (file, offset) = match writer {
+            Some(mut writer) => {
+                // 终点之后多写入的音频不影响回放，保留在文件中
+                writer.flush()?;
+                writer.finalize()?;
+                (segment_file_name(&id), 0)
+            }
+            None => {
+                if let Some(writer) = archive.continuous.as_mut() {
+                    // 更新文件头，使文件在会话结束前也可以读取
+                    writer.flush()?;
+                }
+                (channel_file_name(channel), samples_to_ms(start))
+            }
+        };
+        let segment = ArchivedSegment {
+            id,
+            channel,
+            file,
+            offset,
+            start: samples_to_ms(start),
+            end: samples_to_ms(end),
+            text: text.to_string(),
+        };
+        self.segments.push(segment.clone());
+        let index = serde_json::to_string_pretty(&self.segments)?;
+        std::fs::write(self.dir.join(INDEX_FILE_NAME), index)?;
+        Ok(Some(segment))
+    }
+
+    fn channel(&mut self, channel: usize) -> &mut ChannelArchive {
+        if self.channels.len() <= channel {
+            self.channels
+                .resize_with(channel + 1, ChannelArchive::default);
+        }
+        &mut self.channels[channel]
+    }
+}
+
+/// 读取已保存语音段的音频
+/// # 参数
+/// - `dir`: 存档目录
+/// - `id`: 语音段 id
+/// # 返回值
+/// - 语音段的 WAV 文件数据
+pub fn read_archived_segment(dir: impl AsRef<Path>, id: &str) -> Result<Vec<u8>> {
+    let session = id
+        .split_once('-')
+        .map(|(session, _)| session)
+        .filter(|session| !session.is_empty() && session.chars().all(|c| c.is_ascii_digit()))
+        .ok_or_else(|| anyhow!("无效的语音段 id: {}", id))?;
+    let session_dir = dir.as_ref().join(session);
+    let index = std::fs::read_to_string(session_dir.join(INDEX_FILE_NAME))?;
+    let segments: Vec<ArchivedSegment> = serde_json::from_str(&index)?;
+    let segment = segments
+        .into_iter()
+        .find(|segment| segment.id == id)
+        .ok_or_else(|| anyhow!("语音段不存在: {}", id))?;
+
+    let mut reader = WavReader::open(session_dir.join(&segment.file))?;
+    let start = ms_to_samples(segment.offset);
+    let length = ms_to_samples(segment.end - segment.start);
+    reader.seek(start.min(reader.duration() as u64) as u32)?;
+    let samples = reader
+        .samples::<i16>()
+        .take(length as usize)
+        .collect::<Result<Vec<i16>, _>>()?;
+
+    let mut wav = Cursor::new(Vec::new());
+    {
+        let mut writer = WavWriter::new(&mut wav, WAV_SPEC)?;
+        for sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+    }
+    Ok(wav.into_inner())
+}
+
+fn write_samples(writer: &mut WavWriter<BufWriter<File>>, samples: &[f32]) -> Result<()> {
+    for &sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    Ok(())
+}
+
+fn channel_file_name(channel: usize) -> String {
+    format!("channel-{}.wav", channel)
+}
+
+fn segment_file_name(id: &str) -> String {
+    format!("segment-{}.wav", id)
+}
+
+fn samples_to_ms(samples: u64) -> u64 {
+    samples * 1000 / SAMPLE_RATE as u64
+}
+
+fn ms_to_samples(ms: u64) -> u64 {
+    ms * SAMPLE_RATE as u64 / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4s 音频，每秒的采样值分别为 0.1、0.2、0.3、0.4
+    fn write_fixture(archive: &mut AudioArchive) -> Result<()> {
+        for second in 1..=4 {
+            archive.write(0, &vec![second as f32 / 10.0; SAMPLE_RATE])?;
+            if second == 3 {
+                // 语音起点距离末尾 3s，超过按语音段保存时缓存的 2s
+                archive.start_segment(0, SAMPLE_RATE * 3)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 存档并读取语音段，返回语音段信息与读取到的采样
+    fn round_trip(mode: ArchiveMode) -> (ArchivedSegment, Vec<i16>) {
+        let dir = std::env::temp_dir().join(format!(
+            "subtitles-archive-{:?}-{}",
+            mode,
+            std::process::id()
+        ));
+        let mut archive = AudioArchive::new(&dir, mode).unwrap();
+        write_fixture(&mut archive).unwrap();
+        // 语音终点距离末尾 0.5s
+        let segment = archive
+            .end_segment(0, SAMPLE_RATE / 2, "你好")
+            .unwrap()
+            .unwrap();
+        let wav = read_archived_segment(&dir, &segment.id).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let samples = WavReader::new(Cursor::new(wav))
+            .unwrap()
+            .samples::<i16>()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        (segment, samples)
+    }
+
+    #[test]
+    fn continuous_round_trip() {
+        let (segment, samples) = round_trip(ArchiveMode::Continuous);
+        assert_eq!((segment.start, segment.end, segment.offset), (0, 3500, 0));
+        assert_eq!(segment.file, "channel-0.wav");
+        assert_eq!(samples.len(), SAMPLE_RATE * 7 / 2);
+        assert_eq!(samples[0], (0.1 * i16::MAX as f32) as i16);
+    }
+
+    #[test]
+    fn per_segment_round_trip() {
+        let (segment, samples) = round_trip(ArchiveMode::PerSegment);
+        // 起点按实际写入的 2s 缓存计算
+        assert_eq!(
+            (segment.start, segment.end, segment.offset),
+            (1000, 3500, 0)
+        );
+        assert_eq!(segment.text, "你好");
+        assert_eq!(samples.len(), SAMPLE_RATE * 5 / 2);
+        assert_eq!(samples[0], (0.2 * i16::MAX as f32) as i16);
+    }
+}
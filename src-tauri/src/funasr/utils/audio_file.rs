@@ -0,0 +1,105 @@
+use crate::funasr::utils::constant::SAMPLE_RATE;
+use crate::funasr::utils::{resample, split_channels, ChannelMode};
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// 读取音频文件
+/// 支持 WAV/FLAC/MP3/OGG 格式，多声道音频取平均值合并为单声道，并重采样至 `SAMPLE_RATE`
+/// # 参数
+/// - `path`: 音频文件路径
+/// # 返回值
+/// - 范围为 [-1,1] 的单声道音频数据
+pub fn read_audio_file(path: impl AsRef<Path>) -> Result<Vec<f32>> {
+    let mut channel_data = read_audio_file_channels(path, ChannelMode::Downmix)?;
+    Ok(channel_data.remove(0))
+}
+
+/// 按指定的声道处理方式读取音频文件
+/// # 返回值
+/// - 每个输出声道范围为 [-1,1] 的音频数据
+pub fn read_audio_file_channels(
+    path: impl AsRef<Path>,
+    channel_mode: ChannelMode,
+) -> Result<Vec<Vec<f32>>> {
+    let (samples, channels, sample_rate) = decode_audio_file(path)?;
+    channel_mode.validate(channels)?;
+    split_channels(&samples, channels, channel_mode)
+        .into_iter()
+        .map(|channel_data| resample(channel_data, sample_rate, SAMPLE_RATE as u32))
+        .collect()
+}
+
+/// 解码音频文件
+/// # 返回值
+/// - 交错排列的音频数据、声道数、采样率
+fn decode_audio_file(path: impl AsRef<Path>) -> Result<(Vec<f32>, usize, u32)> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    // 使用文件后缀辅助判断格式
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        stream,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("音频文件中没有可解码的音轨: {}", path.display()))?;
+    let track_id = track.id;
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut samples: Vec<f32> = Vec::new();
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // 读取到文件末尾
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // 损坏的数据包直接跳过
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let spec = *decoded.spec();
+        channels = spec.channels.count();
+        sample_rate = spec.rate;
+        let buffer =
+            sample_buffer.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+        if buffer.capacity() < decoded.capacity() * channels {
+            *buffer = SampleBuffer::new(decoded.capacity() as u64, spec);
+        }
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+    if channels == 0 || sample_rate == 0 {
+        return Err(anyhow!("无法获取音频格式信息: {}", path.display()));
+    }
+    Ok((samples, channels, sample_rate))
+}
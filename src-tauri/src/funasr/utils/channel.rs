@@ -0,0 +1,84 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// 多声道音频的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ChannelMode {
+    /// 所有声道取平均值合并为单声道
+    #[default]
+    Downmix,
+    /// 只使用指定序号的声道，序号从 0 开始
+    Select(usize),
+    /// 每个声道单独输出
+    PerChannel,
+}
+
+impl ChannelMode {
+    /// 输出的声道数
+    /// # 参数
+    /// - `channels`: 输入的声道数
+    pub fn output_channels(&self, channels: usize) -> usize {
+        match self {
+            ChannelMode::Downmix | ChannelMode::Select(_) => 1,
+            ChannelMode::PerChannel => channels.max(1),
+        }
+    }
+
+    /// 检查处理方式是否适用于指定声道数的输入
+    /// # 参数
+    /// - `channels`: 输入的声道数
+    /// # 错误
+    /// - 选择的声道序号超出输入的声道数
+    pub fn validate(&self, channels: usize) -> Result<()> {
+        match self {
+            ChannelMode::Select(index) if *index >= channels => {
+                bail!("声道序号 {} 超出范围，输入只有 {} 个声道", index, channels)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 将交错排列的多声道音频按处理方式拆分
+/// # 参数
+/// - `data`: 交错排列的音频数据
+/// - `channels`: 输入的声道数
+/// - `channel_mode`: 处理方式，需要先通过 `ChannelMode::validate` 检查
+/// # 返回值
+/// - 每个输出声道的音频数据，数量与 `ChannelMode::output_channels` 一致
+pub fn split_channels(data: &[f32], channels: usize, channel_mode: ChannelMode) -> Vec<Vec<f32>> {
+    if channels <= 1 {
+        return vec![data.to_vec(); channel_mode.output_channels(1)];
+    }
+    match channel_mode {
+        ChannelMode::Downmix => vec![data
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()],
+        ChannelMode::Select(index) => vec![data
+            .chunks_exact(channels)
+            .map(|frame| frame[index])
+            .collect()],
+        ChannelMode::PerChannel => (0..channels)
+            .map(|index| {
+                data.chunks_exact(channels)
+                    .map(|frame| frame[index])
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+/// 将多个声道的音频取平均值合并为单声道
+pub fn downmix(channel_data: &[Vec<f32>]) -> Vec<f32> {
+    match channel_data {
+        [] => Vec::new(),
+        [mono] => mono.clone(),
+        _ => {
+            let length = channel_data.iter().map(|c| c.len()).min().unwrap_or(0);
+            (0..length)
+                .map(|i| channel_data.iter().map(|c| c[i]).sum::<f32>() / channel_data.len() as f32)
+                .collect()
+        }
+    }
+}
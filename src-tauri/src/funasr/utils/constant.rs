@@ -1,35 +1,21 @@
-use crate::funasr::utils::{WavFrontend, CMVN};
-use ndarray::{Array1, Array2};
+use crate::funasr::utils::{Fbank, VadConfig, WavFrontend, CMVN};
+use log::{error, info};
 use std::clone::Clone;
-use std::f32::consts::PI;
+use std::path::Path;
 use std::sync::LazyLock;
 
 // 采样频率
 pub const SAMPLE_RATE: usize = 16000;
-// 每帧的长度，单位：ms
-pub const FRAME_LENGTH_MS: usize = 25;
-// 每帧的偏移，单位：ms
-pub const FRAME_SHIFT_MS: usize = 10;
-// 每帧的长度，单位：样本点
-pub const FRAME_LENGTH: usize = SAMPLE_RATE * FRAME_LENGTH_MS / 1000;
-// 每帧的偏移，单位：样本点
-pub const FRAME_SHIFT: usize = SAMPLE_RATE * FRAME_SHIFT_MS / 1000;
-// 梅尔滤波器数量
-pub const MEL_BINS: usize = 80;
-/// 最低频率 (Hz)，滤波器组覆盖的最低频率 通常设为 20Hz 以忽略极低频噪声
-pub const LOW_FREQ: f32 = 20.0;
-/// 最高频率 (Hz) 通常为 fs/2
-pub const HIGH_FREQ: f32 = 8000.0;
-/// 预加重系数，用于平衡高频和低频能量
-pub const PREEMPH_COEFF: f32 = 0.97f32;
-/// 噪声增益 0.00003f32
-// pub const NOISE_GAIN: f32 = 0.00003f32;
-/// 将 FRAME_LENGTH 向上舍入到最近的2的幂次方
-pub const PADDED_SIZE: usize = round_up_to_nearest_power_of_two(FRAME_LENGTH);
-/// 创建梅尔滤波器组系数矩阵
-pub static MEL_BANKS: LazyLock<Array2<f32>> = LazyLock::new(|| create_mel_banks());
-/// 汉明窗
-pub static HAMMING_WINDOW: LazyLock<Array1<f32>> = LazyLock::new(|| hamming_window());
+/// 模型文件目录
+pub const MODEL_DIR: &str = "models";
+/// Filter Bank 特征提取器，端点检测与语音识别共用，使用语音识别模型的 fbank 配置
+/// 端点检测模型的 fbank 配置不一致时，FSMN 端点检测模型加载失败
+pub static FBANK: LazyLock<Fbank> =
+    LazyLock::new(|| Fbank::new(SPEECH_RECOGNITION_WAV_FRONTEND.fbank_options().clone()));
+/// 送入 SenseVoice 的语音段最长帧数 20s，超过时在能量最低的帧处切分
+pub const MAX_SENSE_VOICE_FRAMES: usize = 2000;
+/// 切分过长语音段时，在末尾的 2s 内查找能量最低的帧
+pub const SPLIT_LOOKBACK_FRAMES: usize = 200;
 /// 并行处理的线程数
 pub const INTRA_THREADS: usize = 4;
 /// 语音识别 LFR_M LFR_N
@@ -38,92 +24,25 @@ pub const SPEECH_RECOGNITION_LFR_N: usize = 6;
 /// 端点检测 LFR_M LFR_N
 pub static ENDPOINT_DETECTION_LFR_M: usize = 5;
 pub static ENDPOINT_DETECTION_LFR_N: usize = 1;
-/// 语音端点检测 窗口检测器相关参数
-pub const WIN_SIZE_FRAME: usize = 20; // 滑动窗口大小，单位为帧数
-pub const SIL_TO_SPEECH_FRMCNT_THRES: usize = 15; // 静音到语音帧数阈值
-pub const SPEECH_TO_SIL_FRMCNT_THRES: usize = 15; // 语音到静音帧数阈值
-
-/// 将整数向上舍入到最近的2的幂次方
-const fn round_up_to_nearest_power_of_two(mut n: usize) -> usize {
-    if n <= 1 {
-        return 1;
-    }
-    n -= 1;
-    n |= n >> 1; // 填充最高位右边的1位
-    n |= n >> 2; // 填充最高位右边的2位
-    n |= n >> 4; // 填充最高位右边的4位
-    n |= n >> 8; // 填充最高位右边的8位
-    n |= n >> 16; // 填充最高位右边的16位 (对于64位系统需要再加 >> 32)
-    n + 1
-}
-
-/// 创建梅尔滤波器组系数矩阵
-fn create_mel_banks() -> Array2<f32> {
-    /// 将频率（Hz）转换为梅尔频率
-    fn hz_to_mel(hz: f32) -> f32 {
-        1127.0 * (1.0 + hz / 700.0).ln()
-    }
-
-    /// 将梅尔频率转换为频率（Hz）
-    fn mel_to_hz(mel: f32) -> f32 {
-        700.0 * ((mel / 1127.0).exp() - 1.0)
+/// 语音端点检测配置，读取模型目录中 `vad-config.yaml` 的 `model_conf`，文件不存在时使用默认配置
+pub static VAD_CONFIG: LazyLock<VadConfig> = LazyLock::new(|| {
+    let path = Path::new(MODEL_DIR).join("vad-config.yaml");
+    if !path.exists() {
+        return VadConfig::default();
     }
-
-    // FFT 后的频谱点数（取一半，因为对称性）
-    let n_fft = PADDED_SIZE / 2 + 1;
-
-    // 将频率转换为梅尔频率
-    let low_mel = hz_to_mel(LOW_FREQ);
-    let high_mel = hz_to_mel(HIGH_FREQ);
-
-    // 在梅尔频率上均匀分布 n_mels + 2 个点
-    let mel_points = Array1::linspace(low_mel, high_mel, MEL_BINS + 2);
-
-    // 将梅尔频率转换回赫兹频率
-    let hz_points = mel_points.map(|&mel| mel_to_hz(mel));
-
-    // 将赫兹频率转换为 FFT bin 索引
-    let bin_points = hz_points.map(|&hz| hz * PADDED_SIZE as f32 / SAMPLE_RATE as f32);
-
-    // 创建梅尔滤波器组矩阵
-    let mut mel_banks = Array2::zeros((MEL_BINS, n_fft));
-
-    for m in 0..MEL_BINS {
-        let left = bin_points[m];
-        let center = bin_points[m + 1];
-        let right = bin_points[m + 2];
-        // 确保索引在有效范围内
-        if center >= n_fft as f32 {
-            continue;
+    match VadConfig::load(&path) {
+        Ok(config) => {
+            info!("已加载端点检测配置");
+            config
         }
-        for j in 0..n_fft {
-            mel_banks[[m, j]] = if j as f32 >= left && j as f32 <= center {
-                if center > left {
-                    (j as f32 - left) / (center - left)
-                } else {
-                    0.0
-                }
-            } else if j as f32 > center && j as f32 <= right {
-                if right > center {
-                    (right - j as f32) / (right - center)
-                } else {
-                    0.0
-                }
-            } else {
-                0.0
-            };
+        Err(e) => {
+            error!("加载端点检测配置失败，使用默认配置: {}", e);
+            VadConfig::default()
         }
     }
-    mel_banks
-}
-
-/// 获取hamming窗
-fn hamming_window() -> Array1<f32> {
-    let mut window = Array1::range(0.0, FRAME_LENGTH as f32, 1.0);
-    window.mapv_inplace(|x| 0.54 - 0.46 * (2.0 * PI * x / (FRAME_LENGTH as f32 - 1.0)).cos());
-    window
-}
+});
 
+/// 内置的端点检测模型 CMVN，模型目录中没有 `vad-am.mvn` 时使用
 pub(crate) static ENDPOINT_DETECTION_CMVN: LazyLock<CMVN> = LazyLock::new(|| CMVN {
     means: vec![
         -8.311879, -8.600912, -9.615928, -10.43595, -11.21292, -11.88333, -12.36243, -12.63706,
@@ -231,6 +150,7 @@ pub(crate) static ENDPOINT_DETECTION_CMVN: LazyLock<CMVN> = LazyLock::new(|| CMV
     ],
 });
 
+/// 内置的语音识别模型 CMVN，模型目录中没有 `sense-voice-am.mvn` 时使用
 pub(crate) static SPEECH_RECOGNITION_CMVN: LazyLock<CMVN> = LazyLock::new(|| CMVN {
     means: vec![
         -8.311879, -8.600912, -9.615928, -10.43595, -11.21292, -11.88333, -12.36243, -12.63706,
@@ -378,17 +298,48 @@ pub(crate) static SPEECH_RECOGNITION_CMVN: LazyLock<CMVN> = LazyLock::new(|| CMV
     ],
 });
 
+/// 语音识别前端，读取 `sense-voice-am.mvn` 与 `sense-voice-config.yaml`，实时识别的 paraformer 也使用该前端
 pub static SPEECH_RECOGNITION_WAV_FRONTEND: LazyLock<WavFrontend> = LazyLock::new(|| {
-    WavFrontend::new(
-        SPEECH_RECOGNITION_CMVN.clone(),
-        SPEECH_RECOGNITION_LFR_M,
-        SPEECH_RECOGNITION_LFR_N,
+    load_wav_frontend(
+        "sense-voice",
+        WavFrontend::new(
+            SPEECH_RECOGNITION_CMVN.clone(),
+            SPEECH_RECOGNITION_LFR_M,
+            SPEECH_RECOGNITION_LFR_N,
+        ),
     )
 });
+/// 端点检测前端，读取 `vad-am.mvn` 与 `vad-config.yaml`
 pub static ENDPOINT_DETECTION_WAV_FRONTEND: LazyLock<WavFrontend> = LazyLock::new(|| {
-    WavFrontend::new(
-        ENDPOINT_DETECTION_CMVN.clone(),
-        ENDPOINT_DETECTION_LFR_M,
-        ENDPOINT_DETECTION_LFR_N,
+    load_wav_frontend(
+        "vad",
+        WavFrontend::new(
+            ENDPOINT_DETECTION_CMVN.clone(),
+            ENDPOINT_DETECTION_LFR_M,
+            ENDPOINT_DETECTION_LFR_N,
+        ),
     )
 });
+
+/// 从模型目录加载前端配置，文件不存在或加载失败时使用内置配置
+/// # 参数
+/// - `model_name`: 模型文件名前缀，读取 `{model_name}-am.mvn` 与 `{model_name}-config.yaml`
+/// - `default`: 内置的前端配置
+fn load_wav_frontend(model_name: &str, default: WavFrontend) -> WavFrontend {
+    let model_dir = Path::new(MODEL_DIR);
+    let am_mvn = model_dir.join(format!("{}-am.mvn", model_name));
+    let config = model_dir.join(format!("{}-config.yaml", model_name));
+    if !am_mvn.exists() && !config.exists() {
+        return default;
+    }
+    match WavFrontend::load(&am_mvn, &config, &default) {
+        Ok(frontend) => {
+            info!("已加载 {} 的前端配置", model_name);
+            frontend
+        }
+        Err(e) => {
+            error!("加载 {} 的前端配置失败，使用内置配置: {}", model_name, e);
+            default
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::funasr::utils::constant::SAMPLE_RATE;
+use crate::funasr::utils::resampler::gcd;
+use crate::funasr::utils::ChannelMode;
+use anyhow::Result;
+use cpal::traits::DeviceTrait;
+use cpal::{Device, SampleFormat, SampleRate, SupportedStreamConfig, SupportedStreamConfigRange};
+use serde::{Deserialize, Serialize};
+
+/// 采样率范围内优先尝试的常见采样率
+const COMMON_SAMPLE_RATES: [u32; 8] = [16000, 32000, 48000, 96000, 8000, 22050, 44100, 88200];
+
+/// 输入设备支持的一组配置
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceConfigRange {
+    /// 声道数
+    pub channels: u16,
+    /// 最低采样率
+    pub min_sample_rate: u32,
+    /// 最高采样率
+    pub max_sample_rate: u32,
+    /// 采样格式
+    pub sample_format: String,
+}
+
+/// 输入设备的具体配置
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceConfig {
+    /// 声道数
+    pub channels: u16,
+    /// 采样率
+    pub sample_rate: u32,
+    /// 采样格式
+    pub sample_format: String,
+}
+
+/// 输入设备的配置信息，用于排查设备问题
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceConfigs {
+    /// 设备名称
+    pub name: String,
+    /// 设备的默认配置
+    pub default_config: Option<DeviceConfig>,
+    /// 设备支持的全部配置
+    pub supported_configs: Vec<DeviceConfigRange>,
+    /// 录音时实际使用的配置
+    pub best_config: Option<DeviceConfig>,
+    /// 获取配置失败时的错误信息
+    pub error: Option<String>,
+}
+
+impl From<&SupportedStreamConfig> for DeviceConfig {
+    fn from(config: &SupportedStreamConfig) -> Self {
+        Self {
+            channels: config.channels(),
+            sample_rate: config.sample_rate().0,
+            sample_format: config.sample_format().to_string(),
+        }
+    }
+}
+
+impl From<&SupportedStreamConfigRange> for DeviceConfigRange {
+    fn from(range: &SupportedStreamConfigRange) -> Self {
+        Self {
+            channels: range.channels(),
+            min_sample_rate: range.min_sample_rate().0,
+            max_sample_rate: range.max_sample_rate().0,
+            sample_format: range.sample_format().to_string(),
+        }
+    }
+}
+
+/// 选择最适合识别的输入配置
+/// 优先选择满足声道处理方式、采样率等于目标采样率、f32 或 i16 格式的配置；
+/// 没有目标采样率时选择重采样比例最简单的采样率。
+/// 无法获取支持的配置时使用设备的默认配置
+/// # 参数
+/// - `device`: 输入设备
+/// - `target_sample_rate`: 识别使用的采样率
+/// - `channel_mode`: 多声道音频的处理方式
+pub fn best_input_config(
+    device: &Device,
+    target_sample_rate: u32,
+    channel_mode: ChannelMode,
+) -> Result<SupportedStreamConfig> {
+    let best_config = device.supported_input_configs().ok().and_then(|ranges| {
+        ranges
+            .filter_map(|range| {
+                let sample_rate = best_sample_rate(&range, target_sample_rate);
+                let score = config_score(&range, sample_rate, target_sample_rate, channel_mode)?;
+                Some((score, range.with_sample_rate(SampleRate(sample_rate))))
+            })
+            .min_by_key(|(score, _)| *score)
+            .map(|(_, config)| config)
+    });
+    match best_config {
+        Some(config) => Ok(config),
+        None => Ok(device.default_input_config()?),
+    }
+}
+
+/// 输入设备支持的最大声道数
+pub fn input_channels(device: &Device) -> Result<usize> {
+    let channels = device
+        .supported_input_configs()
+        .ok()
+        .and_then(|ranges| ranges.map(|range| range.channels() as usize).max());
+    match channels {
+        Some(channels) => Ok(channels),
+        None => Ok(device.default_input_config()?.channels() as usize),
+    }
+}
+
+/// 获取输入设备的全部配置信息
+/// # 参数
+/// - `device`: 输入设备
+/// - `channel_mode`: 多声道音频的处理方式，用于计算录音时实际使用的配置
+pub fn device_configs(device: &Device, channel_mode: ChannelMode) -> DeviceConfigs {
+    let default_config = device
+        .default_input_config()
+        .ok()
+        .map(|config| DeviceConfig::from(&config));
+    let (supported_configs, error) = match device.supported_input_configs() {
+        Ok(ranges) => (
+            ranges
+                .map(|range| DeviceConfigRange::from(&range))
+                .collect(),
+            None,
+        ),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let best_config = best_input_config(device, SAMPLE_RATE as u32, channel_mode)
+        .ok()
+        .map(|config| DeviceConfig::from(&config));
+    DeviceConfigs {
+        name: device.name().unwrap_or_default(),
+        default_config,
+        supported_configs,
+        best_config,
+        error,
+    }
+}
+
+/// 采样率范围内最适合的采样率
+fn best_sample_rate(range: &SupportedStreamConfigRange, target_sample_rate: u32) -> u32 {
+    let min = range.min_sample_rate().0;
+    let max = range.max_sample_rate().0;
+    COMMON_SAMPLE_RATES
+        .into_iter()
+        .chain([target_sample_rate, min, max])
+        .filter(|rate| (min..=max).contains(rate))
+        .min_by_key(|&rate| sample_rate_score(rate, target_sample_rate))
+        .unwrap_or(max)
+}
+
+/// 采样率的评分，越小越好
+/// 低于目标采样率时会损失高频信息，排在最后；
+/// 其余按化简后的插值倍数与抽取倍数之和排序，如 48kHz 转 16kHz 为 1/3
+fn sample_rate_score(sample_rate: u32, target_sample_rate: u32) -> (bool, usize) {
+    let divisor = gcd(sample_rate as usize, target_sample_rate as usize).max(1);
+    let up = target_sample_rate as usize / divisor;
+    let down = sample_rate as usize / divisor;
+    (sample_rate < target_sample_rate, up + down)
+}
+
+/// 配置的评分，越小越好，不满足声道处理方式时返回 None
+/// 依次比较：分别识别时的声道数、采样率、合并或选择声道时的声道数、采样格式
+fn config_score(
+    range: &SupportedStreamConfigRange,
+    sample_rate: u32,
+    target_sample_rate: u32,
+    channel_mode: ChannelMode,
+) -> Option<(usize, (bool, usize), usize, usize)> {
+    let channels = range.channels() as usize;
+    // 分别识别时声道越多越好，合并为单声道或选择声道时声道越少越好
+    let (per_channel_score, channel_score) = match channel_mode {
+        ChannelMode::Downmix => (0, channels),
+        ChannelMode::Select(index) if index >= channels => return None,
+        ChannelMode::Select(_) => (0, channels),
+        ChannelMode::PerChannel => (usize::MAX - channels, 0),
+    };
+    let format_score = match range.sample_format() {
+        SampleFormat::F32 => 0,
+        SampleFormat::I16 => 1,
+        SampleFormat::I32 => 2,
+        // 24 位整数需要额外转换一次
+        SampleFormat::I24 => 3,
+        _ => 4,
+    };
+    Some((
+        per_channel_score,
+        sample_rate_score(sample_rate, target_sample_rate),
+        channel_score,
+        format_score,
+    ))
+}
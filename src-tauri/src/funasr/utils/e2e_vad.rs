@@ -1,14 +1,209 @@
-use crate::funasr::utils::constant::{
-    SIL_TO_SPEECH_FRMCNT_THRES, SPEECH_TO_SIL_FRMCNT_THRES, WIN_SIZE_FRAME,
-};
-use crate::funasr::utils::fbank::Frame;
+use anyhow::{anyhow, Result};
 use ndarray::{Array2, ArrayView1};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-const NOISE_FRAME_NUM_USED_FOR_SNR: f32 = 100.0;
-const SNR_THRES: f32 = -100.0; // 信噪比阈值(dB) - 低于此值的帧被认为是噪声
-const DECIBEL_THRES: f32 = -100.0; // 分贝阈值(dB) - 音频能量阈值，低于此值的帧被认为是静音
-const SPEECH_NOISE_THRES: f32 = 0.6; // 语音/噪声概率阈值 - 神经网络输出的概率判别阈值
-const SPEECH_2_NOISE_RATIO: f32 = 1.0; // 语音/噪声比 - 用于调整语音和噪声的判别阈值
+/// 语音段检测模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum DetectMode {
+    /// 只检测一个语音段，之后不再检测，与 FunASR 的 `detect_mode: 0` 对应
+    SingleUtterance,
+    /// 持续检测多个语音段，与 FunASR 的 `detect_mode: 1` 对应
+    #[default]
+    MultipleUtterance,
+}
+
+impl TryFrom<u8> for DetectMode {
+    type Error = String;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DetectMode::SingleUtterance),
+            1 => Ok(DetectMode::MultipleUtterance),
+            _ => Err(format!("不支持的检测模式: {}", value)),
+        }
+    }
+}
+
+impl From<DetectMode> for u8 {
+    fn from(mode: DetectMode) -> Self {
+        match mode {
+            DetectMode::SingleUtterance => 0,
+            DetectMode::MultipleUtterance => 1,
+        }
+    }
+}
+
+/// 语音端点检测的配置
+/// 字段与 FunASR 端点检测模型 `config.yaml` 中的 `model_conf` 一致，时间单位均为 ms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    /// 语音段检测模式
+    pub detect_mode: DetectMode,
+    /// 语音段结束前允许的最长静音时长
+    pub max_end_silence_time: u64,
+    /// 语音段开始前允许的最长静音时长，仅在 `SingleUtterance` 模式下生效，超时后不再检测
+    pub max_start_silence_time: u64,
+    /// 单个语音段的最长时长，超过时强制结束语音段
+    pub max_single_segment_time: u64,
+    /// 滑动窗口的时长
+    pub window_size_ms: u64,
+    /// 窗口内的语音时长达到该值时由静音转为语音
+    pub sil_to_speech_time_thres: u64,
+    /// 窗口内的语音时长不超过该值时由语音转为静音
+    pub speech_to_sil_time_thres: u64,
+    /// 语音起点向前扩展的时长，为 0 时不扩展
+    pub lookback_time_start_point: u64,
+    /// 语音终点向后扩展的时长，为 0 时不扩展
+    pub lookahead_time_end_point: u64,
+    /// 语音/噪声比，用于调整语音和噪声的判别阈值
+    pub speech_2_noise_ratio: f32,
+    /// 信噪比阈值(dB)，低于此值的帧被认为是噪声
+    pub snr_thres: f32,
+    /// 计算噪声平均分贝值使用的帧数
+    pub noise_frame_num_used_for_snr: f32,
+    /// 分贝阈值(dB)，低于此值的帧被认为是静音
+    pub decibel_thres: f32,
+    /// 语音/噪声概率阈值，神经网络输出的概率判别阈值
+    pub speech_noise_thres: f32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            detect_mode: DetectMode::MultipleUtterance,
+            max_end_silence_time: 800,
+            max_start_silence_time: 3000,
+            max_single_segment_time: 60000,
+            window_size_ms: 200,
+            sil_to_speech_time_thres: 150,
+            speech_to_sil_time_thres: 150,
+            lookback_time_start_point: 200,
+            lookahead_time_end_point: 100,
+            speech_2_noise_ratio: 1.0,
+            snr_thres: -100.0,
+            noise_frame_num_used_for_snr: 100.0,
+            decibel_thres: -100.0,
+            speech_noise_thres: 0.6,
+        }
+    }
+}
+
+/// 语音端点检测的灵敏度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum VadSensitivity {
+    /// 低灵敏度，适用于嘈杂环境，减少误检并尽快结束语音段
+    Low,
+    /// 中灵敏度，使用模型目录中的配置
+    #[default]
+    Medium,
+    /// 高灵敏度，适用于音量较小的说话人
+    High,
+    /// 自定义配置
+    Custom,
+}
+
+impl VadSensitivity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VadSensitivity::Low => "低",
+            VadSensitivity::Medium => "中",
+            VadSensitivity::High => "高",
+            VadSensitivity::Custom => "自定义",
+        }
+    }
+
+    pub fn all() -> Vec<VadSensitivity> {
+        vec![
+            VadSensitivity::Low,
+            VadSensitivity::Medium,
+            VadSensitivity::High,
+            VadSensitivity::Custom,
+        ]
+    }
+
+    pub fn from_name(name: &str) -> Result<VadSensitivity> {
+        Self::all()
+            .into_iter()
+            .find(|sensitivity| sensitivity.name() == name)
+            .ok_or_else(|| anyhow!("无法识别的灵敏度: {}", name))
+    }
+
+    /// 在 `base` 配置上应用灵敏度预设，`Medium` 与 `Custom` 不修改配置
+    pub fn apply(&self, base: &VadConfig) -> VadConfig {
+        let mut config = base.clone();
+        match self {
+            VadSensitivity::Low => {
+                config.speech_noise_thres = 0.8;
+                config.snr_thres = 10.0;
+                config.sil_to_speech_time_thres = config.window_size_ms * 9 / 10;
+                config.max_end_silence_time = config
+                    .max_end_silence_time
+                    .min(500)
+                    .max(config.speech_to_sil_time_thres);
+            }
+            VadSensitivity::High => {
+                config.speech_noise_thres = 0.3;
+                config.snr_thres = -100.0;
+                config.decibel_thres = -100.0;
+                config.sil_to_speech_time_thres = config.window_size_ms / 2;
+                config.max_end_silence_time = config.max_end_silence_time.max(1000);
+            }
+            VadSensitivity::Medium | VadSensitivity::Custom => {}
+        }
+        config
+    }
+}
+
+/// FunASR 模型 `config.yaml` 中与端点检测相关的配置
+#[derive(Deserialize, Default)]
+struct ModelConfig {
+    #[serde(default)]
+    model_conf: VadConfig,
+}
+
+impl VadConfig {
+    /// 读取 FunASR 端点检测模型 `config.yaml` 中的 `model_conf`，未配置的项使用默认值
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        let config: ModelConfig = serde_yaml::from_str(&content)?;
+        config.model_conf.validate()?;
+        Ok(config.model_conf)
+    }
+
+    /// 语音起点相对确认语音的帧最多向前扩展的时长
+    /// 窗口内的语音帧达到阈值才确认语音，起点为窗口的第一帧再向前扩展 `lookback_time_start_point`
+    pub fn start_lookback_ms(&self) -> u64 {
+        self.window_size_ms + self.lookback_time_start_point
+    }
+
+    /// 检查配置是否有效
+    pub fn validate(&self) -> Result<()> {
+        if self.window_size_ms == 0 {
+            return Err(anyhow!("滑动窗口的时长不能为 0"));
+        }
+        if self.sil_to_speech_time_thres > self.window_size_ms
+            || self.speech_to_sil_time_thres > self.window_size_ms
+        {
+            return Err(anyhow!(
+                "静音/语音转换阈值 {}ms/{}ms 不能超过滑动窗口的时长 {}ms",
+                self.sil_to_speech_time_thres,
+                self.speech_to_sil_time_thres,
+                self.window_size_ms
+            ));
+        }
+        if self.max_end_silence_time < self.speech_to_sil_time_thres {
+            return Err(anyhow!(
+                "语音段结束前的最长静音时长 {}ms 不能小于语音转静音阈值 {}ms",
+                self.max_end_silence_time,
+                self.speech_to_sil_time_thres
+            ));
+        }
+        Ok(())
+    }
+}
 
 /// 帧状态枚举
 /// 定义每一帧音频的分类结果
@@ -57,20 +252,34 @@ pub struct WindowDetector {
 
     /// 前一帧状态
     pre_frame_state: FrameState,
+
+    /// 静音到语音帧数阈值
+    sil_to_speech_frmcnt_thres: usize,
+
+    /// 语音到静音帧数阈值
+    speech_to_sil_frmcnt_thres: usize,
 }
 
-impl Default for WindowDetector {
-    fn default() -> Self {
+impl WindowDetector {
+    /// # 参数
+    /// * `win_size_frame` - 滑动窗口大小，单位为帧数
+    /// * `sil_to_speech_frmcnt_thres` - 静音到语音帧数阈值
+    /// * `speech_to_sil_frmcnt_thres` - 语音到静音帧数阈值
+    pub fn new(
+        win_size_frame: usize,
+        sil_to_speech_frmcnt_thres: usize,
+        speech_to_sil_frmcnt_thres: usize,
+    ) -> Self {
         Self {
-            win_sum: 20,
-            win_state: vec![0; 20], // 初始化窗口状态为20个静音帧
+            win_sum: 0,
+            win_state: vec![0; win_size_frame.max(1)], // 初始化窗口状态为静音帧
             cur_win_pos: 0,
             pre_frame_state: FrameState::KFrameStateSil,
+            sil_to_speech_frmcnt_thres,
+            speech_to_sil_frmcnt_thres,
         }
     }
-}
 
-impl WindowDetector {
     /// 重置检测器状态
     /// 清空所有计数器和状态信息
     pub fn reset(&mut self) {
@@ -99,11 +308,11 @@ impl WindowDetector {
         self.win_sum -= self.win_state[self.cur_win_pos]; // 如果上一帧是语音，则减去1，否则减去0
         self.win_sum += cur_frame_state; // 如果当前帧是语音，则加上1，否则加上0
         self.win_state[self.cur_win_pos] = cur_frame_state; // 更新当前帧的状态
-        self.cur_win_pos = (self.cur_win_pos + 1) % WIN_SIZE_FRAME; // 更新当前窗口位置 +1
+        self.cur_win_pos = (self.cur_win_pos + 1) % self.win_state.len(); // 更新当前窗口位置 +1
 
         // 检测静音到语音的转换
         if self.pre_frame_state == FrameState::KFrameStateSil
-            && self.win_sum >= SIL_TO_SPEECH_FRMCNT_THRES
+            && self.win_sum >= self.sil_to_speech_frmcnt_thres
         {
             self.pre_frame_state = FrameState::KFrameStateSpeech;
             return AudioChangeState::KChangeStateSil2Speech;
@@ -111,7 +320,7 @@ impl WindowDetector {
 
         // 检测语音到静音的转换
         if self.pre_frame_state == FrameState::KFrameStateSpeech
-            && self.win_sum <= SPEECH_TO_SIL_FRMCNT_THRES
+            && self.win_sum <= self.speech_to_sil_frmcnt_thres
         {
             self.pre_frame_state = FrameState::KFrameStateSil;
             return AudioChangeState::KChangeStateSpeech2Sil;
@@ -139,73 +348,136 @@ pub enum PointType {
 #[derive(Debug, Clone)]
 pub struct Segment {
     pub segment_type: PointType,
+    /// 语音段起点，单位：ms，从会话开始计算
+    pub start: u64,
+    /// 语音段终点，单位：ms，语音段起点事件中为 None
+    pub end: Option<u64>,
 }
+/// 语音端点检测状态机的状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VadState {
+    /// 尚未检测到语音起点
+    StartPointNotDetected,
+    /// 处于语音段中
+    InSpeechSegment,
+    /// 已检测到语音终点
+    EndPointDetected,
+}
+
+/// FunASR 的 E2E 语音端点检测状态机
+/// 由滑动窗口检测器判断静音/语音的转换，再根据静音时长、语音段时长确定语音段的起点与终点
 pub struct E2EVadModel {
+    /// 端点检测配置
+    config: VadConfig,
     /// 滑动窗口检测器，用于平滑检测结果
     windows_detector: WindowDetector,
     /// 噪声平均分贝值，用于动态调整阈值
     noise_average_decibel: f32,
+    /// 每帧得分对应的音频时长，单位：ms
+    frame_ms: f32,
+    /// 会话开始以来已检测的帧数
+    frame_index: usize,
+    /// 状态机的状态
+    state: VadState,
+    /// 当前语音段的起始帧
+    speech_start: usize,
+    /// 上一个语音段的终止帧，下一个语音段的起点不会早于该帧
+    last_speech_end: usize,
+    /// 连续的静音帧数
+    continuous_silence_frames: usize,
 }
 
-impl Default for E2EVadModel {
-    fn default() -> Self {
-        Self {
-            windows_detector: WindowDetector {
-                win_sum: 0,
-                win_state: vec![0; 20], // 初始化窗口状态为20个静音帧
-                cur_win_pos: 0,
-                pre_frame_state: FrameState::KFrameStateSil,
-            },
+impl E2EVadModel {
+    /// # 参数
+    /// * `config`: 端点检测配置
+    /// * `frame_ms`: 每帧得分对应的音频时长，即 fbank 帧移乘以 LFR 步长
+    pub fn new(config: VadConfig, frame_ms: f32) -> Self {
+        let mut model = Self {
+            config: VadConfig::default(),
+            windows_detector: WindowDetector::new(0, 0, 0),
             noise_average_decibel: -100.0, // 初始噪声分贝值设为-100dB
-        }
+            frame_ms,
+            frame_index: 0,
+            state: VadState::StartPointNotDetected,
+            speech_start: 0,
+            last_speech_end: 0,
+            continuous_silence_frames: 0,
+        };
+        model.set_config(config);
+        model
     }
-}
 
-impl E2EVadModel {
+    /// 会话开始以来已检测的音频时长，单位：ms
+    pub fn elapsed_ms(&self) -> u64 {
+        self.frame_to_ms(self.frame_index)
+    }
+
+    /// 修改端点检测配置，正在检测的语音段继续使用新的配置
+    /// 滑动窗口按新的窗口大小重新开始
+    pub fn set_config(&mut self, config: VadConfig) {
+        let to_frames = |ms: u64| (ms as f32 / self.frame_ms) as usize;
+        self.windows_detector = WindowDetector::new(
+            to_frames(config.window_size_ms),
+            to_frames(config.sil_to_speech_time_thres),
+            to_frames(config.speech_to_sil_time_thres),
+        );
+        self.config = config;
+    }
+
+    fn frame_to_ms(&self, frame: usize) -> u64 {
+        (frame as f32 * self.frame_ms) as u64
+    }
+
+    fn ms_to_frames(&self, ms: u64) -> usize {
+        (ms as f32 / self.frame_ms) as usize
+    }
+
+    /// 重置检测状态，准备检测下一个语音段
     pub fn reset(&mut self) {
         self.windows_detector.reset();
+        self.state = VadState::StartPointNotDetected;
+        self.continuous_silence_frames = 0;
     }
+
     /// E2E VAD主调用接口
     /// 处理输入的音频数据和神经网络得分，返回检测到的语音段
     /// ## 参数
-    ///     * `scores`: 神经网络输出的得分矩阵 [time_steps, num_classes] num_classes为 248
-    ///     * `waveform`: 特征的原始音频信号，该数据从`WavFrontend`中调用函数返回
-    pub fn call(&mut self, scores: Array2<f32>, frames: &Vec<Frame>) -> Vec<Segment> {
-        let (frame_count, _) = scores.dim();
-        // 计算音频分贝值序列
+    ///     * `scores`: 神经网络输出的得分矩阵 [time_steps, num_classes]，第一列为静音的概率
+    ///     * `decibels`: 每帧得分对应音频的分贝值
+    pub fn call(&mut self, scores: Array2<f32>, decibels: &[f32]) -> Vec<Segment> {
         let mut segments: Vec<Segment> = Vec::new();
         // 进行帧检测
-        for i in 0..frame_count {
-            let score = scores.row(i);
-            let decibel = frames[i].decibel;
+        for (score, &decibel) in scores.rows().into_iter().zip(decibels) {
             // 获取当前帧的语音状态
             let frame_state = self.get_frame_state(score, decibel);
             // 检测单帧的状态变化并更新VAD状态机
             if let Some(segment) = self.detect_one_frame(frame_state) {
                 segments.push(segment);
             }
+            self.frame_index += 1;
         }
         segments
     }
 
     /// 判断当前帧的语音状态
     fn get_frame_state(&mut self, score: ArrayView1<f32>, decibel: f32) -> FrameState {
+        let config = &self.config;
         // 初始化帧状态
         // 计算当前帧的信噪比（SNR）= 当前音量 - 噪声平均音量
         let snr = decibel - self.noise_average_decibel;
         // 如果当前帧的音量低于设定的阈值，则直接判定为静音帧
-        if decibel < -100.0 {
+        if decibel < config.decibel_thres {
             return FrameState::KFrameStateSil;
         }
         let p_silence: f32 = score[0];
         let p_speech: f32 = 1.0 - p_silence;
-        let p_silence = p_silence.ln() * SPEECH_2_NOISE_RATIO;
+        let p_silence = p_silence.ln() * config.speech_2_noise_ratio;
         let p_speech = p_speech.ln();
 
-        // 判断当前帧的语音概率是否高于静音概率+阈值 speech_noise_thres 0.6
-        if p_speech.exp() >= p_silence.exp() + SPEECH_NOISE_THRES {
-            // 信噪比 SNR 大于设定的阈值 -100 ，并且 分贝值大于设定的阈值 -100，则判断为语音帧
-            if snr >= SNR_THRES && decibel >= DECIBEL_THRES {
+        // 判断当前帧的语音概率是否高于静音概率+阈值 speech_noise_thres
+        if p_speech.exp() >= p_silence.exp() + config.speech_noise_thres {
+            // 信噪比和分贝值均不低于设定的阈值，则判断为语音帧
+            if snr >= config.snr_thres && decibel >= config.decibel_thres {
                 FrameState::KFrameStateSpeech
             } else {
                 FrameState::KFrameStateSil
@@ -214,10 +486,8 @@ impl E2EVadModel {
             if self.noise_average_decibel < -99.9 {
                 self.noise_average_decibel = decibel;
             } else {
-                // noise_frame_num_used_for_snr 为 100
-                self.noise_average_decibel = (decibel
-                    + self.noise_average_decibel * (NOISE_FRAME_NUM_USED_FOR_SNR - 1.0))
-                    / NOISE_FRAME_NUM_USED_FOR_SNR;
+                let n = config.noise_frame_num_used_for_snr;
+                self.noise_average_decibel = (decibel + self.noise_average_decibel * (n - 1.0)) / n;
             }
             FrameState::KFrameStateSil
         }
@@ -227,40 +497,186 @@ impl E2EVadModel {
     /// # 参数
     /// * `frame_state`: 当前帧的语音状态
     fn detect_one_frame(&mut self, frame_state: FrameState) -> Option<Segment> {
+        let cur_frame = self.frame_index;
         // 使用窗口检测器
         let state_change = self.windows_detector.detect_one_frame(frame_state);
 
-        let mut state: Option<Segment> = None;
-        // 根据状态变化进行相应的处理 TODO
+        let mut segment: Option<Segment> = None;
         match state_change {
-            AudioChangeState::KChangeStateSpeech2Speech => {
-                // 语音到语音，持续语音段，无需特殊处理
+            AudioChangeState::KChangeStateSil2Speech => {
+                // 静音到语音，开始新的语音段
+                self.continuous_silence_frames = 0;
+                match self.state {
+                    VadState::StartPointNotDetected => {
+                        // 窗口内的语音帧才使状态改变，起点为窗口的第一帧再向前扩展
+                        let latency = self.ms_to_frames(self.config.window_size_ms)
+                            + self.ms_to_frames(self.config.lookback_time_start_point);
+                        self.speech_start =
+                            cur_frame.saturating_sub(latency).max(self.last_speech_end);
+                        self.state = VadState::InSpeechSegment;
+                        segment = Some(Segment {
+                            segment_type: PointType::Start,
+                            start: self.frame_to_ms(self.speech_start),
+                            end: None,
+                        });
+                    }
+                    VadState::InSpeechSegment => segment = self.check_max_segment(cur_frame),
+                    VadState::EndPointDetected => {}
+                }
             }
-            AudioChangeState::KChangeStateSpeech2Sil => {
-                // 语音到静音，结束当前语音段
-                state = Some(Segment {
-                    segment_type: PointType::End,
-                });
+            AudioChangeState::KChangeStateSpeech2Sil
+            | AudioChangeState::KChangeStateSpeech2Speech => {
+                // 语音到静音时窗口内仍有语音，语音段的终点由之后的连续静音决定
+                self.continuous_silence_frames = 0;
+                if self.state == VadState::InSpeechSegment {
+                    segment = self.check_max_segment(cur_frame);
+                }
             }
             AudioChangeState::KChangeStateSil2Sil => {
-                // 静音到静音，持续静音段，无需特殊处理
-            }
-            AudioChangeState::KChangeStateSil2Speech => {
-                // 静音到语音，开始新的语音段
-                state = Some(Segment {
-                    segment_type: PointType::Start,
-                });
+                self.continuous_silence_frames += 1;
+                let silence_ms = self.frame_to_ms(self.continuous_silence_frames);
+                match self.state {
+                    VadState::StartPointNotDetected => {
+                        // 单语音段模式下，开始前静音过长则不再检测
+                        if self.config.detect_mode == DetectMode::SingleUtterance
+                            && silence_ms > self.config.max_start_silence_time
+                        {
+                            self.state = VadState::EndPointDetected;
+                        }
+                    }
+                    VadState::InSpeechSegment => {
+                        // 窗口检测器转为静音时已经过了 speech_to_sil_time_thres
+                        let max_end_silence =
+                            self.config.max_end_silence_time - self.config.speech_to_sil_time_thres;
+                        if silence_ms >= max_end_silence {
+                            // 终点回退到静音开始处，再向后扩展 lookahead_time_end_point
+                            let lookback = self.ms_to_frames(max_end_silence).saturating_sub(
+                                self.ms_to_frames(self.config.lookahead_time_end_point) + 1,
+                            );
+                            segment = Some(self.on_voice_end(cur_frame.saturating_sub(lookback)));
+                        } else {
+                            segment = self.check_max_segment(cur_frame);
+                        }
+                    }
+                    VadState::EndPointDetected => {}
+                }
             }
             AudioChangeState::KChangeStateInvalid => {
                 // 无效状态变化或未开始，无需处理
             }
         }
-        // 多语音段检测模式：如果检测到语音结束且配置为多段模式，重置状态准备下一段
-        if let Some(segment) = &state {
-            if matches!(segment.segment_type, PointType::End) {
-                self.reset();
-            }
+        // 多语音段检测模式：检测到语音终点后重置状态准备下一段
+        if self.state == VadState::EndPointDetected
+            && self.config.detect_mode == DetectMode::MultipleUtterance
+        {
+            self.reset();
         }
-        state
+        segment
+    }
+
+    /// 语音段超过最长时长时在当前帧强制结束
+    fn check_max_segment(&mut self, cur_frame: usize) -> Option<Segment> {
+        let max_frames = self.ms_to_frames(self.config.max_single_segment_time);
+        if cur_frame - self.speech_start + 1 > max_frames {
+            Some(self.on_voice_end(cur_frame))
+        } else {
+            None
+        }
+    }
+
+    /// 结束当前语音段
+    fn on_voice_end(&mut self, end_frame: usize) -> Segment {
+        let end_frame = end_frame.max(self.speech_start);
+        self.last_speech_end = end_frame;
+        self.state = VadState::EndPointDetected;
+        Segment {
+            segment_type: PointType::End,
+            start: self.frame_to_ms(self.speech_start),
+            end: Some(self.frame_to_ms(end_frame)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每帧得分对应的音频时长
+    const FRAME_MS: f32 = 10.0;
+
+    /// 按帧的语音/静音序列构造得分并检测
+    fn detect(model: &mut E2EVadModel, speech: &[bool]) -> Vec<Segment> {
+        let scores = Array2::from_shape_fn(
+            (speech.len(), 1),
+            |(i, _)| {
+                if speech[i] {
+                    0.05
+                } else {
+                    0.95
+                }
+            },
+        );
+        model.call(scores, &vec![0.0; speech.len()])
+    }
+
+    /// `ms` 时长的语音或静音
+    fn frames(speech: bool, ms: usize) -> Vec<bool> {
+        vec![speech; ms / FRAME_MS as usize]
+    }
+
+    #[test]
+    fn forces_end_at_max_single_segment_time() {
+        let config = VadConfig {
+            max_single_segment_time: 1000,
+            ..VadConfig::default()
+        };
+        let mut model = E2EVadModel::new(config, FRAME_MS);
+        let segments = detect(&mut model, &frames(true, 3000));
+        let types: Vec<PointType> = segments.iter().map(|s| s.segment_type).collect();
+        use PointType::{End, Start};
+        assert_eq!(types, [Start, End, Start, End, Start]);
+        assert_eq!(segments[1].end, Some(1000));
+        // 强制结束后的语音段紧接上一段，不会与上一段重叠
+        assert_eq!(segments[2].start, 1000);
+        assert_eq!(segments[3].end, Some(2000));
+    }
+
+    #[test]
+    fn end_point_backs_off_to_silence_start() {
+        let mut model = E2EVadModel::new(VadConfig::default(), FRAME_MS);
+        let segments = detect(&mut model, &frames(true, 1000));
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].segment_type, PointType::Start);
+        assert_eq!(segments[0].start, 0);
+
+        // 静音不足 max_end_silence_time 时语音段不结束
+        assert!(detect(&mut model, &frames(false, 690)).is_empty());
+        let segments = detect(&mut model, &frames(false, 10));
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].segment_type, PointType::End);
+        assert_eq!(model.elapsed_ms(), 1700);
+        // 终点回退到静音开始处，再向后扩展 lookahead_time_end_point
+        assert_eq!(segments[0].end, Some(1150));
+    }
+
+    #[test]
+    fn single_utterance_stops_after_start_silence_timeout() {
+        let config = VadConfig {
+            detect_mode: DetectMode::SingleUtterance,
+            max_start_silence_time: 500,
+            ..VadConfig::default()
+        };
+
+        // 超时之前出现的语音正常检测
+        let mut model = E2EVadModel::new(config.clone(), FRAME_MS);
+        assert!(detect(&mut model, &frames(false, 300)).is_empty());
+        let segments = detect(&mut model, &frames(true, 500));
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].segment_type, PointType::Start);
+
+        // 超时之后不再检测语音
+        let mut model = E2EVadModel::new(config, FRAME_MS);
+        assert!(detect(&mut model, &frames(false, 1000)).is_empty());
+        assert!(detect(&mut model, &frames(true, 2000)).is_empty());
     }
 }
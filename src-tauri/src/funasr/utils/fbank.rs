@@ -1,112 +1,697 @@
-use crate::funasr::utils::constant::{
-    FRAME_LENGTH, FRAME_SHIFT, HAMMING_WINDOW, MEL_BANKS, PADDED_SIZE, PREEMPH_COEFF,
-};
+use crate::funasr::utils::constant::SAMPLE_RATE;
 use ndarray;
-use ndarray::{s, Array1, Axis, Zip};
+use ndarray::{s, Array1, Array2, ArrayViewMut1, Axis};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use rustfft::{num_complex::Complex, Fft, FftPlanner};
-use std::sync::{Arc, LazyLock};
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-static FFT: LazyLock<Arc<dyn Fft<f32>>> = LazyLock::new(|| {
-    let mut planner = FftPlanner::new();
-    planner.plan_fft_forward(PADDED_SIZE)
-});
+/// 窗函数类型
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum WindowType {
+    /// 汉明窗
+    #[default]
+    Hamming,
+    /// Kaldi 默认使用的 povey 窗，汉宁窗的 0.85 次方
+    Povey,
+    /// 汉宁窗
+    Hann,
+    /// 矩形窗
+    Rectangular,
+}
+
+/// Filter Bank 特征的提取参数，字段含义与 Kaldi 的 fbank 参数一致
+/// 默认值与 FunASR 使用 kaldi-native-fbank 时的配置一致
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FbankOptions {
+    /// 采样频率
+    pub sample_rate: usize,
+    /// 每帧的长度，单位：ms
+    pub frame_length_ms: f32,
+    /// 每帧的偏移，单位：ms
+    pub frame_shift_ms: f32,
+    /// 梅尔滤波器数量
+    pub mel_bins: usize,
+    /// 最低频率 (Hz)，滤波器组覆盖的最低频率 通常设为 20Hz 以忽略极低频噪声
+    pub low_freq: f32,
+    /// 最高频率 (Hz)，小于等于 0 时表示相对奈奎斯特频率的偏移
+    pub high_freq: f32,
+    /// 预加重系数，用于平衡高频和低频能量
+    pub preemph_coeff: f32,
+    /// 窗函数类型
+    pub window_type: WindowType,
+    /// 是否移除直流分量
+    pub remove_dc_offset: bool,
+    /// 是否将 FFT 长度向上舍入到2的幂次方
+    pub round_to_power_of_two: bool,
+    /// 为 true 时只输出完整位于音频内的帧；
+    /// 为 false 时帧以帧移的中点对齐，音频开头不足的部分使用镜像填充
+    pub snip_edges: bool,
+    /// 抖动系数，为每个采样添加标准差为该值的高斯噪声，0 表示不添加
+    pub dither: f32,
+    /// 是否在特征的第一维添加对数能量
+    pub use_energy: bool,
+    /// 对数能量的下限，0 表示不限制
+    pub energy_floor: f32,
+    /// 为 true 时在预加重和加窗之前计算能量
+    pub raw_energy: bool,
+    /// 为 true 时与 kaldi-native-fbank 的计算结果一致；
+    /// 为 false 时使用早期的简化实现：首个采样不做预加重、梅尔滤波器按 FFT bin 插值
+    pub kaldi_compatible: bool,
+}
+
+impl Default for FbankOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: SAMPLE_RATE,
+            frame_length_ms: 25.0,
+            frame_shift_ms: 10.0,
+            mel_bins: 80,
+            low_freq: 20.0,
+            high_freq: 0.0,
+            preemph_coeff: 0.97,
+            window_type: WindowType::Hamming,
+            remove_dc_offset: true,
+            round_to_power_of_two: true,
+            snip_edges: true,
+            dither: 0.0,
+            use_energy: false,
+            energy_floor: 0.0,
+            raw_energy: true,
+            kaldi_compatible: true,
+        }
+    }
+}
+
+impl FbankOptions {
+    /// 每帧的长度，单位：样本点
+    pub fn frame_length(&self) -> usize {
+        (self.sample_rate as f32 * self.frame_length_ms / 1000.0) as usize
+    }
+
+    /// 每帧的偏移，单位：样本点
+    pub fn frame_shift(&self) -> usize {
+        (self.sample_rate as f32 * self.frame_shift_ms / 1000.0) as usize
+    }
+
+    /// 每帧特征的维度
+    pub fn feature_dim(&self) -> usize {
+        self.mel_bins + self.use_energy as usize
+    }
+
+    /// FFT 长度
+    pub fn padded_size(&self) -> usize {
+        if self.round_to_power_of_two {
+            self.frame_length().next_power_of_two()
+        } else {
+            self.frame_length()
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Frame {
-    pub feature: Array1<f32>, // 梅尔频谱
-    pub decibel: f32,         // 帧的分贝值
+    pub feature: Array1<f32>,    // 梅尔频谱，use_energy 时第一维为对数能量
+    pub decibel: f32,            // 帧的分贝值
+    pub zero_crossing_rate: f32, // 帧的过零率
 }
 
-impl Frame {
-    pub fn new(mut waveform: Array1<f32>) -> Self {
-        let decibel = compute_decibel(&waveform);
+/// 梅尔三角滤波器，只保存非零的权重
+struct MelFilter {
+    /// 第一个非零权重对应的 FFT bin
+    offset: usize,
+    weights: Vec<f32>,
+}
+
+/// 计算帧特征时复用的缓冲区，避免每帧重新分配
+struct Scratch {
+    /// 帧的音频数据
+    window: Vec<f32>,
+    /// 实数 FFT 的输入，长度为 FFT 长度
+    fft_input: Vec<f32>,
+    /// 实数 FFT 的输出，长度为 FFT 长度的一半加一
+    spectrum: Vec<Complex<f32>>,
+    fft_scratch: Vec<Complex<f32>>,
+    /// 功率谱
+    power_spectrum: Vec<f32>,
+    /// 抖动使用的随机数状态
+    rng: u64,
+}
+
+/// Filter Bank 特征提取器
+/// 根据 `FbankOptions` 预先计算窗函数、梅尔滤波器组和 FFT
+pub struct Fbank {
+    options: FbankOptions,
+    frame_length: usize,
+    frame_shift: usize,
+    window: Vec<f32>,
+    mel_filters: Vec<MelFilter>,
+    fft: Arc<dyn RealToComplex<f32>>,
+    /// 为每个缓冲区生成不同的随机数种子
+    rng_seed: AtomicU64,
+}
+
+impl Fbank {
+    pub fn new(options: FbankOptions) -> Self {
+        let frame_length = options.frame_length();
+        let frame_shift = options.frame_shift();
+        let padded_size = options.padded_size();
+        let window = create_window(options.window_type, frame_length);
+        let mel_banks = if options.kaldi_compatible {
+            create_kaldi_mel_banks(&options, padded_size)
+        } else {
+            create_mel_banks(&options, padded_size)
+        };
+        let fft = RealFftPlanner::new().plan_fft_forward(padded_size);
+        Self {
+            options,
+            frame_length,
+            frame_shift,
+            window,
+            mel_filters: sparse_mel_filters(&mel_banks),
+            fft,
+            rng_seed: AtomicU64::new(0x853c_49e6_748f_ea9b),
+        }
+    }
+
+    /// 特征提取参数
+    pub fn options(&self) -> &FbankOptions {
+        &self.options
+    }
+
+    /// 每帧的长度，单位：样本点
+    pub fn frame_length(&self) -> usize {
+        self.frame_length
+    }
+
+    /// 每帧的偏移，单位：样本点
+    pub fn frame_shift(&self) -> usize {
+        self.frame_shift
+    }
+
+    /// 计算 Filter Bank 特征
+    /// # 参数
+    /// - `waveform`: 音频数据
+    /// - `is_start`: 是否为音频的开头，`snip_edges` 为 false 时在开头进行镜像填充
+    /// # 返回值
+    /// - 音频帧
+    /// - 剩余的音频数据，下次计算时拼接在音频数据之前
+    pub fn compute(&self, waveform: Array1<f32>, is_start: bool) -> (Vec<Frame>, Array1<f32>) {
+        let (waveform, frame_count) = self.split_frames(waveform, is_start);
+        let mut scratch = self.scratch();
+        let frames = (0..frame_count)
+            .map(|i| self.frame_at(&mut scratch, &waveform, i))
+            .collect();
+        let remaining_waveform = waveform
+            .slice(s![frame_count * self.frame_shift..])
+            .into_owned();
+        (frames, remaining_waveform)
+    }
+
+    /// 多线程计算 Filter Bank 特征，用于一次性处理较长的音频，参数与返回值同 `compute`
+    pub fn compute_parallel(
+        &self,
+        waveform: Array1<f32>,
+        is_start: bool,
+    ) -> (Vec<Frame>, Array1<f32>) {
+        let (waveform, frame_count) = self.split_frames(waveform, is_start);
+        let frames = (0..frame_count)
+            .into_par_iter()
+            .map_init(
+                || self.scratch(),
+                |scratch, i| self.frame_at(scratch, &waveform, i),
+            )
+            .collect();
+        let remaining_waveform = waveform
+            .slice(s![frame_count * self.frame_shift..])
+            .into_owned();
+        (frames, remaining_waveform)
+    }
+
+    /// 音频结束时计算剩余的帧
+    /// `snip_edges` 为 false 时，帧数与 Kaldi 一致，超出音频末尾的部分使用镜像填充；
+    /// 为 true 时剩余的音频不足一帧，不输出
+    /// # 参数
+    /// - `remaining_waveform`: 最后一次计算剩余的音频数据
+    pub fn finish(&self, remaining_waveform: Array1<f32>) -> Vec<Frame> {
+        if self.options.snip_edges || remaining_waveform.is_empty() {
+            return Vec::new();
+        }
+        // 剩余音频的开头是下一帧的起点，音频末尾之后还有半个帧移内的帧中点
+        let pad = (self.frame_length / 2) as isize - (self.frame_shift / 2) as isize;
+        let length = remaining_waveform.len() as isize;
+        let frame_count =
+            (length - pad + (self.frame_shift / 2) as isize).max(0) as usize / self.frame_shift;
+        let mut scratch = self.scratch();
+        (0..frame_count)
+            .map(|i| {
+                let start = i * self.frame_shift;
+                for (j, x) in scratch.window.iter_mut().enumerate() {
+                    *x = remaining_waveform[reflect_index(start + j, remaining_waveform.len())];
+                }
+                self.frame(&mut scratch)
+            })
+            .collect()
+    }
+
+    /// 开头填充后计算可以完整提取的帧数
+    fn split_frames(&self, waveform: Array1<f32>, is_start: bool) -> (Array1<f32>, usize) {
+        let waveform = if is_start && !self.options.snip_edges {
+            self.reflect_pad(waveform)
+        } else {
+            waveform
+        };
+        // 如果音频数据的长度小于窗口长度，则没有完整的帧
+        let frame_count = if waveform.len() < self.frame_length {
+            0
+        } else {
+            1 + (waveform.len() - self.frame_length) / self.frame_shift
+        };
+        (waveform, frame_count)
+    }
+
+    fn scratch(&self) -> Scratch {
+        let seed = self
+            .rng_seed
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed);
+        Scratch {
+            window: vec![0.0; self.frame_length],
+            fft_input: self.fft.make_input_vec(),
+            spectrum: self.fft.make_output_vec(),
+            fft_scratch: self.fft.make_scratch_vec(),
+            power_spectrum: vec![0.0; self.fft.len() / 2 + 1],
+            // xorshift 的状态不能为 0
+            rng: seed | 1,
+        }
+    }
+
+    /// 计算第 `index` 帧的特征
+    fn frame_at(&self, scratch: &mut Scratch, waveform: &Array1<f32>, index: usize) -> Frame {
+        let start = index * self.frame_shift;
+        let samples = waveform.slice(s![start..start + self.frame_length]);
+        for (x, &sample) in scratch.window.iter_mut().zip(samples) {
+            *x = sample;
+        }
+        self.frame(scratch)
+    }
+
+    /// 计算 `scratch.window` 中一帧音频的特征
+    fn frame(&self, scratch: &mut Scratch) -> Frame {
+        let options = &self.options;
+        let decibel = compute_decibel(&scratch.window);
+        let zero_crossing_rate = compute_zero_crossing_rate(&scratch.window);
+        if options.dither != 0.0 {
+            for x in scratch.window.iter_mut() {
+                *x += options.dither * rand_gauss(&mut scratch.rng);
+            }
+        }
+        let waveform = &mut scratch.window;
         // 移除直流分量，消除信号中的直流偏移
-        let mean = waveform.mean().unwrap();
-        waveform.mapv_inplace(|x| x - mean);
+        if options.remove_dc_offset {
+            let mean = waveform.iter().sum::<f32>() / waveform.len() as f32;
+            waveform.iter_mut().for_each(|x| *x -= mean);
+        }
+        let use_energy = options.use_energy;
+        let raw_log_energy =
+            (use_energy && options.raw_energy).then(|| compute_log_energy(waveform));
 
         // 预加重 preemph_coeff
-        for j in (1..waveform.len()).rev() {
-            waveform[j] -= PREEMPH_COEFF * waveform[j - 1];
-        }
-        let hamming_window = HAMMING_WINDOW.to_owned();
-        // 应用汉明窗
-        Zip::from(&mut waveform)
-            .and(&hamming_window)
-            .for_each(|x, &h| {
-                *x *= h;
-            });
+        let preemph_coeff = options.preemph_coeff;
+        if preemph_coeff != 0.0 {
+            for j in (1..waveform.len()).rev() {
+                waveform[j] -= preemph_coeff * waveform[j - 1];
+            }
+            if options.kaldi_compatible {
+                waveform[0] -= preemph_coeff * waveform[0];
+            }
+        }
+        // 应用窗函数
+        for (x, &h) in waveform.iter_mut().zip(&self.window) {
+            *x *= h;
+        }
+        let log_energy = match raw_log_energy {
+            Some(log_energy) => log_energy,
+            None if use_energy => compute_log_energy(waveform),
+            None => 0.0,
+        };
+
         // 计算特征
-        let feature = compute_features(waveform);
-        Self { feature, decibel }
+        let mut feature = Array1::zeros(options.feature_dim());
+        let mel_energies = if use_energy {
+            feature[0] = if options.energy_floor > 0.0 {
+                log_energy.max(options.energy_floor.ln())
+            } else {
+                log_energy
+            };
+            feature.slice_mut(s![1..])
+        } else {
+            feature.view_mut()
+        };
+        self.compute_features(scratch, mel_energies);
+        Frame {
+            feature,
+            decibel,
+            zero_crossing_rate,
+        }
     }
-}
 
-/// 计算 Filter Bank 特征
-pub fn fbank(waveform: Array1<f32>) -> (Vec<Frame>, Array1<f32>) {
-    let waveform_length = waveform.len();
-    // 如果音频数据的长度小于窗口长度，则返回空
-    if waveform_length < FRAME_LENGTH {
-        return (Vec::new(), waveform);
+    /// 计算加窗后音频的对数梅尔频谱
+    fn compute_features(&self, scratch: &mut Scratch, mut mel_energies: ArrayViewMut1<f32>) {
+        // 将输入补零到 FFT 长度
+        let length = scratch.window.len();
+        scratch.fft_input[..length].copy_from_slice(&scratch.window);
+        scratch.fft_input[length..].fill(0.0);
+        self.fft
+            .process_with_scratch(
+                &mut scratch.fft_input,
+                &mut scratch.spectrum,
+                &mut scratch.fft_scratch,
+            )
+            .expect("FFT 缓冲区长度与计划不一致");
+        // 计算功率谱
+        for (power, c) in scratch.power_spectrum.iter_mut().zip(&scratch.spectrum) {
+            *power = c.norm_sqr();
+        }
+
+        // 对每个梅尔滤波器进行计算，Kaldi 以 f32 的机器精度为对数的下限
+        let floor = if self.options.kaldi_compatible {
+            f32::EPSILON
+        } else {
+            1e-10
+        };
+        for (energy, filter) in mel_energies.iter_mut().zip(&self.mel_filters) {
+            let power_spectrum = &scratch.power_spectrum[filter.offset..];
+            let sum: f32 = filter
+                .weights
+                .iter()
+                .zip(power_spectrum)
+                .map(|(&weight, &power)| weight * power)
+                .sum();
+            *energy = sum.max(floor).ln();
+        }
     }
-    let frame_count = 1 + (waveform_length - FRAME_LENGTH) / FRAME_SHIFT;
-    // 获取剩余的数据
-    let remaining_waveform: Array1<f32> =
-        waveform.slice(s![frame_count * FRAME_SHIFT..]).into_owned();
-    let mut frames: Vec<Frame> = Vec::new();
-    for i in 0..frame_count {
-        let frame = Frame::new(
-            waveform
-                .slice(s![i * FRAME_SHIFT..i * FRAME_SHIFT + FRAME_LENGTH])
-                .into_owned(),
-        );
-        frames.push(frame)
+
+    /// 在音频开头镜像填充，使第一帧的中心位于第一个帧移的中点
+    fn reflect_pad(&self, waveform: Array1<f32>) -> Array1<f32> {
+        let pad = (self.frame_length / 2).saturating_sub(self.frame_shift / 2);
+        if pad == 0 || waveform.is_empty() {
+            return waveform;
+        }
+        let last = waveform.len() - 1;
+        (0..pad)
+            .rev()
+            .map(|i| waveform[i.min(last)])
+            .chain(waveform.iter().copied())
+            .collect()
     }
-    (frames, remaining_waveform)
 }
 
-fn compute_features(window: Array1<f32>) -> Array1<f32> {
-    // 将输入调整到2的幂
-    let mut fft_input = Array1::zeros(PADDED_SIZE);
-    fft_input.slice_mut(s![..FRAME_LENGTH]).assign(&window);
-    let mut buffer: Vec<Complex<f32>> = fft_input.iter().map(|&x| Complex::new(x, 0.0)).collect();
-    FFT.process(&mut buffer);
-    let power_spectrum = compute_power_spectrum(&buffer);
+/// 生成标准正态分布的随机数
+fn rand_gauss(rng: &mut u64) -> f32 {
+    let u1 = rand_uniform(rng).max(f32::MIN_POSITIVE);
+    let u2 = rand_uniform(rng);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// 生成 [0,1) 的均匀分布随机数
+fn rand_uniform(rng: &mut u64) -> f32 {
+    // xorshift64，抖动不需要高质量的随机数
+    let mut x = *rng;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *rng = x;
+    (x >> 40) as f32 / (1u64 << 24) as f32
+}
 
-    let mut mel_energies = compute_mel(&power_spectrum);
+/// 去掉滤波器组系数矩阵中每个滤波器两端的零
+fn sparse_mel_filters(mel_banks: &Array2<f32>) -> Vec<MelFilter> {
+    mel_banks
+        .axis_iter(Axis(0))
+        .map(|bank| {
+            let first = bank.iter().position(|&w| w != 0.0);
+            let last = bank.iter().rposition(|&w| w != 0.0);
+            match (first, last) {
+                (Some(first), Some(last)) => MelFilter {
+                    offset: first,
+                    weights: bank.slice(s![first..=last]).to_vec(),
+                },
+                _ => MelFilter {
+                    offset: 0,
+                    weights: Vec::new(),
+                },
+            }
+        })
+        .collect()
+}
 
-    // 应用对数变换
-    mel_energies.mapv_inplace(|x| x.max(1e-10).ln());
-    mel_energies
+/// 将频率（Hz）转换为梅尔频率
+fn mel_scale(hz: f32) -> f32 {
+    1127.0 * (1.0 + hz / 700.0).ln()
 }
 
-/// 计算梅尔频谱
-fn compute_mel(power_spectrum: &Array1<f32>) -> Array1<f32> {
-    let mut mel_energies = Array1::zeros(MEL_BANKS.shape()[0]);
-    // 对每个梅尔滤波器进行计算
-    for (i, mel_bank) in MEL_BANKS.axis_iter(Axis(0)).enumerate() {
-        let mut sum = 0.0;
-        for (j, &mel_filter) in mel_bank.iter().enumerate() {
-            sum += power_spectrum[j] * mel_filter;
+/// 按 Kaldi 的方式创建梅尔滤波器组系数矩阵
+/// 三角滤波器在梅尔频率上线性变化，不包含奈奎斯特频率所在的 bin
+fn create_kaldi_mel_banks(options: &FbankOptions, padded_size: usize) -> Array2<f32> {
+    let mel_bins = options.mel_bins;
+    let num_fft_bins = padded_size / 2;
+    let sample_rate = options.sample_rate as f32;
+    let high_freq = if options.high_freq > 0.0 {
+        options.high_freq
+    } else {
+        sample_rate / 2.0 + options.high_freq
+    };
+    let fft_bin_width = sample_rate / padded_size as f32;
+    let low_mel = mel_scale(options.low_freq);
+    let high_mel = mel_scale(high_freq);
+    let mel_delta = (high_mel - low_mel) / (mel_bins + 1) as f32;
+
+    let mut mel_banks = Array2::zeros((mel_bins, num_fft_bins + 1));
+    for m in 0..mel_bins {
+        let left = low_mel + m as f32 * mel_delta;
+        let center = low_mel + (m + 1) as f32 * mel_delta;
+        let right = low_mel + (m + 2) as f32 * mel_delta;
+        for j in 0..num_fft_bins {
+            let mel = mel_scale(fft_bin_width * j as f32);
+            if mel > left && mel < right {
+                mel_banks[[m, j]] = if mel <= center {
+                    (mel - left) / (center - left)
+                } else {
+                    (right - mel) / (right - center)
+                };
+            }
         }
-        mel_energies[i] = sum;
     }
-    mel_energies
+    mel_banks
 }
 
-/// 计算功率谱
-fn compute_power_spectrum(complex_fft: &Vec<Complex<f32>>) -> Array1<f32> {
-    let mut power_spectrum = Array1::zeros(complex_fft.len());
-    for (i, &c) in complex_fft.iter().enumerate() {
-        power_spectrum[i] = c.norm_sqr();
+/// 创建梅尔滤波器组系数矩阵，三角滤波器按 FFT bin 线性插值
+fn create_mel_banks(options: &FbankOptions, padded_size: usize) -> Array2<f32> {
+    /// 将梅尔频率转换为频率（Hz）
+    fn mel_to_hz(mel: f32) -> f32 {
+        700.0 * ((mel / 1127.0).exp() - 1.0)
     }
-    power_spectrum
+
+    let mel_bins = options.mel_bins;
+    // FFT 后的频谱点数（取一半，因为对称性）
+    let n_fft = padded_size / 2 + 1;
+    let nyquist = options.sample_rate as f32 / 2.0;
+    let high_freq = if options.high_freq > 0.0 {
+        options.high_freq
+    } else {
+        nyquist + options.high_freq
+    };
+
+    // 将频率转换为梅尔频率
+    let low_mel = mel_scale(options.low_freq);
+    let high_mel = mel_scale(high_freq);
+
+    // 在梅尔频率上均匀分布 n_mels + 2 个点
+    let mel_points = Array1::linspace(low_mel, high_mel, mel_bins + 2);
+
+    // 将梅尔频率转换回赫兹频率
+    let hz_points = mel_points.map(|&mel| mel_to_hz(mel));
+
+    // 将赫兹频率转换为 FFT bin 索引
+    let bin_points = hz_points.map(|&hz| hz * padded_size as f32 / options.sample_rate as f32);
+
+    // 创建梅尔滤波器组矩阵
+    let mut mel_banks = Array2::zeros((mel_bins, n_fft));
+
+    for m in 0..mel_bins {
+        let left = bin_points[m];
+        let center = bin_points[m + 1];
+        let right = bin_points[m + 2];
+        // 确保索引在有效范围内
+        if center >= n_fft as f32 {
+            continue;
+        }
+        for j in 0..n_fft {
+            mel_banks[[m, j]] = if j as f32 >= left && j as f32 <= center {
+                if center > left {
+                    (j as f32 - left) / (center - left)
+                } else {
+                    0.0
+                }
+            } else if j as f32 > center && j as f32 <= right {
+                if right > center {
+                    (right - j as f32) / (right - center)
+                } else {
+                    0.0
+                }
+            } else {
+                0.0
+            };
+        }
+    }
+    mel_banks
+}
+
+/// 创建窗函数
+/// 与 Kaldi 一致，使用 f64 计算后转换为 f32
+fn create_window(window_type: WindowType, frame_length: usize) -> Vec<f32> {
+    let a = 2.0 * std::f64::consts::PI / (frame_length as f64 - 1.0);
+    (0..frame_length)
+        .map(|i| {
+            let x = i as f64;
+            let value = match window_type {
+                WindowType::Hamming => 0.54 - 0.46 * (a * x).cos(),
+                WindowType::Povey => (0.5 - 0.5 * (a * x).cos()).powf(0.85),
+                WindowType::Hann => 0.5 - 0.5 * (a * x).cos(),
+                WindowType::Rectangular => 1.0,
+            };
+            value as f32
+        })
+        .collect()
+}
+
+/// 超出音频范围的采样按 Kaldi 的方式镜像到音频内
+fn reflect_index(index: usize, length: usize) -> usize {
+    let mut index = index;
+    while index >= length {
+        index = (2 * length - 1).saturating_sub(index);
+    }
+    index
+}
+
+/// 计算对数能量
+fn compute_log_energy(waveform: &[f32]) -> f32 {
+    let energy: f32 = waveform.iter().map(|x| x * x).sum();
+    energy.max(f32::EPSILON).ln()
 }
 
 /// 计算分贝
-fn compute_decibel(waveform: &Array1<f32>) -> f32 {
+fn compute_decibel(waveform: &[f32]) -> f32 {
     // 计算能量
-    let energy = waveform.map(|x| x.powi(2)).sum();
+    let energy: f32 = waveform.iter().map(|x| x.powi(2)).sum();
     // 转换为分贝值，添加小常数避免log(0)
     10.0 * (energy + 1e-10).log10()
 }
+
+/// 计算过零率，即相邻采样符号改变的比例
+fn compute_zero_crossing_rate(waveform: &[f32]) -> f32 {
+    if waveform.len() < 2 {
+        return 0.0;
+    }
+    // 以均值为零点，避免直流偏移影响
+    let mean = waveform.iter().sum::<f32>() / waveform.len() as f32;
+    let crossings = waveform
+        .windows(2)
+        .filter(|pair| (pair[0] - mean).signum() != (pair[1] - mean).signum())
+        .count();
+    crossings as f32 / (waveform.len() - 1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::concatenate;
+    use std::path::Path;
+
+    /// `tests/fbank/generate.py` 使用 kaldi-native-fbank 生成的参考特征
+    #[derive(Deserialize)]
+    struct Reference {
+        /// 生成参考特征的实现
+        generator: String,
+        options: FbankOptions,
+        samples: Vec<f32>,
+        features: Vec<Vec<f32>>,
+    }
+
+    /// 特征的允许误差，FFT 使用 f32 计算，对数能量较小的滤波器误差较大
+    const TOLERANCE: f32 = 1e-4;
+
+    /// 按 `chunk_size` 分块计算全部音频的特征
+    fn compute_all(fbank: &Fbank, samples: &[f32], chunk_size: usize) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        let mut remaining = Array1::zeros(0);
+        for (i, chunk) in samples.chunks(chunk_size).enumerate() {
+            let waveform = concatenate![Axis(0), remaining, Array1::from_vec(chunk.to_vec())];
+            let (chunk_frames, chunk_remaining) = fbank.compute(waveform, i == 0);
+            frames.extend(chunk_frames);
+            remaining = chunk_remaining;
+        }
+        frames.extend(fbank.finish(remaining));
+        frames
+    }
+
+    /// 与 `tests/fbank/{name}.json` 中的参考特征比较
+    fn check_reference(name: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fbank")
+            .join(format!("{}.json", name));
+        let json = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("读取参考特征 {} 失败: {}", path.display(), e));
+        let reference: Reference = serde_json::from_str(&json).unwrap();
+        // 参考特征必须来自 Kaldi 的实现，否则无法验证一致性
+        assert_eq!(reference.generator, "kaldi-native-fbank");
+        let fbank = Fbank::new(reference.options);
+        let (mut parallel_frames, remaining) =
+            fbank.compute_parallel(Array1::from_vec(reference.samples.clone()), true);
+        parallel_frames.extend(fbank.finish(remaining));
+        let results = [
+            compute_all(&fbank, &reference.samples, reference.samples.len()),
+            compute_all(&fbank, &reference.samples, 1000),
+            parallel_frames,
+        ];
+        for frames in results {
+            assert_eq!(frames.len(), reference.features.len());
+            for (i, (frame, expected)) in frames.iter().zip(&reference.features).enumerate() {
+                assert_eq!(frame.feature.len(), expected.len());
+                for (j, (&actual, &expected)) in frame.feature.iter().zip(expected).enumerate() {
+                    let error = (actual - expected).abs() / expected.abs().max(1.0);
+                    assert!(
+                        error < TOLERANCE,
+                        "第 {} 帧第 {} 维: {} != {}",
+                        i,
+                        j,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "需要先使用 kaldi-native-fbank 运行 tests/fbank/generate.py 生成参考特征"]
+    fn funasr_16k_hamming() {
+        check_reference("funasr_16k_hamming");
+    }
+
+    #[test]
+    #[ignore = "需要先使用 kaldi-native-fbank 运行 tests/fbank/generate.py 生成参考特征"]
+    fn kaldi_16k_povey_no_snip() {
+        check_reference("kaldi_16k_povey_no_snip");
+    }
+
+    #[test]
+    #[ignore = "需要先使用 kaldi-native-fbank 运行 tests/fbank/generate.py 生成参考特征"]
+    fn telephony_8k_hann() {
+        check_reference("telephony_8k_hann");
+    }
+}
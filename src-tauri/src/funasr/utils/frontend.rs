@@ -1,6 +1,12 @@
-use crate::funasr::utils::constant::MEL_BINS;
-use crate::funasr::utils::fbank::Frame;
-use ndarray::{concatenate, s, Array2, ArrayView1, Axis};
+use crate::funasr::utils::constant::SAMPLE_RATE;
+use crate::funasr::utils::fbank::{FbankOptions, Frame, WindowType};
+use anyhow::{anyhow, Result};
+use ndarray::{s, Array2, ArrayViewMut1, Axis};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
 
 /// Cepstral Mean and Variance Normalization
 #[derive(Clone)]
@@ -9,6 +15,193 @@ pub struct CMVN {
     pub vars: Vec<f32>,
 }
 
+impl CMVN {
+    /// 读取 Kaldi nnet 文本格式的 `am.mvn` 文件
+    /// `<AddShift>` 之后的向量为均值，`<Rescale>` 之后的向量为方差倒数
+    pub fn from_am_mvn(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        Self::parse_am_mvn(&content)
+    }
+
+    /// 解析 `am.mvn` 文件内容
+    pub fn parse_am_mvn(content: &str) -> Result<Self> {
+        let means = read_component_vector(content, "<AddShift>")?;
+        let vars = read_component_vector(content, "<Rescale>")?;
+        if means.len() != vars.len() {
+            return Err(anyhow!(
+                "am.mvn 中均值与方差的维度不一致: {} != {}",
+                means.len(),
+                vars.len()
+            ));
+        }
+        Ok(Self { means, vars })
+    }
+}
+
+/// 倒谱均值方差归一化的方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CmvnMode {
+    /// 使用模型训练集的全局统计量
+    #[default]
+    Global,
+    /// 使用当前会话最近音频帧的统计量，以全局统计量作为先验
+    /// 适用于远场、低质量麦克风等与训练数据差异较大的输入
+    Online(OnlineCmvnOptions),
+}
+
+/// 在线 CMVN 的配置
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnlineCmvnOptions {
+    /// 统计最近的音频帧数，默认600帧(6s)
+    pub window: usize,
+    /// 全局统计量的权重，相当于的音频帧数，默认200帧
+    /// 会话开始时音频帧较少，统计量主要由全局统计量决定
+    pub prior_weight: f32,
+}
+
+impl Default for OnlineCmvnOptions {
+    fn default() -> Self {
+        Self {
+            window: 600,
+            prior_weight: 200.0,
+        }
+    }
+}
+
+/// VAD 与语音识别前端各自使用的 CMVN 方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CmvnConfig {
+    pub vad: CmvnMode,
+    pub recognition: CmvnMode,
+}
+
+/// 在线 CMVN 的会话统计量
+/// 按音频帧的每一维统计，LFR 拼接后的各段共用同一维的统计量
+#[derive(Clone)]
+struct OnlineCmvn {
+    options: OnlineCmvnOptions,
+    /// 窗口内的音频帧
+    frames: VecDeque<Arc<Frame>>,
+    /// 窗口内音频帧每一维的和
+    sum: Vec<f64>,
+    /// 窗口内音频帧每一维的平方和
+    sum_sq: Vec<f64>,
+    /// 全局统计量的均值
+    prior_mean: Vec<f64>,
+    /// 全局统计量的二阶矩
+    prior_sq: Vec<f64>,
+}
+
+impl OnlineCmvn {
+    fn new(cmvn: &CMVN, options: OnlineCmvnOptions) -> Self {
+        // am.mvn 中保存的是均值的相反数与标准差的倒数
+        let prior_mean: Vec<f64> = cmvn.means.iter().map(|&m| -m as f64).collect();
+        let prior_sq = prior_mean
+            .iter()
+            .zip(&cmvn.vars)
+            .map(|(&mean, &var)| mean * mean + 1.0 / (var as f64 * var as f64))
+            .collect();
+        Self {
+            options,
+            frames: VecDeque::new(),
+            sum: Vec::new(),
+            sum_sq: Vec::new(),
+            prior_mean,
+            prior_sq,
+        }
+    }
+
+    /// 将音频帧加入统计，超出窗口的音频帧从统计中移除
+    fn update(&mut self, frames: &[Arc<Frame>]) {
+        for frame in frames {
+            if self.sum.len() != frame.feature.len() {
+                self.sum = vec![0.0; frame.feature.len()];
+                self.sum_sq = vec![0.0; frame.feature.len()];
+                self.frames.clear();
+            }
+            accumulate(&mut self.sum, &mut self.sum_sq, frame, 1.0);
+            self.frames.push_back(Arc::clone(frame));
+        }
+        while self.frames.len() > self.options.window {
+            if let Some(frame) = self.frames.pop_front() {
+                accumulate(&mut self.sum, &mut self.sum_sq, &frame, -1.0);
+            }
+        }
+    }
+
+    /// 由会话统计量与全局统计量得到当前的 CMVN
+    fn cmvn(&self, global: &CMVN) -> CMVN {
+        let count = self.frames.len() as f64;
+        let weight = self.options.prior_weight.max(0.0) as f64;
+        let dim = self.sum.len();
+        if count + weight <= 0.0 || dim == 0 {
+            return global.clone();
+        }
+        let (means, vars) = (0..self.prior_mean.len())
+            .map(|d| {
+                let mean = (weight * self.prior_mean[d] + self.sum[d % dim]) / (count + weight);
+                let sq = (weight * self.prior_sq[d] + self.sum_sq[d % dim]) / (count + weight);
+                let var = (sq - mean * mean).max(1e-10);
+                (-mean as f32, (1.0 / var.sqrt()) as f32)
+            })
+            .unzip();
+        CMVN { means, vars }
+    }
+}
+
+/// 将音频帧的特征累加到统计量中，`sign` 为 -1 时移除
+fn accumulate(sum: &mut [f64], sum_sq: &mut [f64], frame: &Frame, sign: f64) {
+    for ((s, sq), &x) in sum.iter_mut().zip(sum_sq.iter_mut()).zip(&frame.feature) {
+        let x = x as f64;
+        *s += sign * x;
+        *sq += sign * x * x;
+    }
+}
+
+/// 读取 nnet 组件之后第一个 `[ ... ]` 中的向量
+fn read_component_vector(content: &str, component: &str) -> Result<Vec<f32>> {
+    let mut tokens = content
+        .split_whitespace()
+        .skip_while(|&token| token != component)
+        .skip_while(|&token| token != "[");
+    if tokens.next().is_none() {
+        return Err(anyhow!("am.mvn 中缺少 {}", component));
+    }
+    tokens
+        .take_while(|&token| token != "]")
+        .map(|token| {
+            token
+                .parse::<f32>()
+                .map_err(|e| anyhow!("am.mvn 中 {} 的数值 {} 无效: {}", component, token, e))
+        })
+        .collect()
+}
+
+/// FunASR 模型 `config.yaml` 中与前端相关的配置
+#[derive(Deserialize, Default)]
+struct ModelConfig {
+    #[serde(default)]
+    frontend_conf: FrontendConf,
+}
+
+/// `config.yaml` 中的 `frontend_conf`，未配置的项使用默认值
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FrontendConf {
+    fs: Option<usize>,
+    window: Option<String>,
+    n_mels: Option<usize>,
+    frame_length: Option<f32>,
+    frame_shift: Option<f32>,
+    lfr_m: Option<usize>,
+    lfr_n: Option<usize>,
+    dither: Option<f32>,
+    snip_edges: Option<bool>,
+}
+
+#[derive(Clone)]
 pub struct WavFrontend {
     /// 均值方差归一化
     cmvn: CMVN,
@@ -18,60 +211,211 @@ pub struct WavFrontend {
     /// LFR帧移步长，默认1
     /// LFR处理时的步长，控制降采样的程度
     lfr_n: usize,
+    /// 模型训练时使用的 fbank 配置
+    fbank_options: FbankOptions,
 }
 
 impl WavFrontend {
     pub fn new(cmvn: CMVN, lfr_m: usize, lfr_n: usize) -> Self {
-        let frontend = Self { cmvn, lfr_m, lfr_n };
+        let frontend = Self {
+            cmvn,
+            lfr_m,
+            lfr_n,
+            fbank_options: FbankOptions::default(),
+        };
         frontend
     }
+
+    /// 从模型目录中的 `am.mvn` 与 `config.yaml` 加载前端配置
+    /// 文件不存在时使用 `default` 中对应的配置
+    /// # 参数
+    /// - `am_mvn`: 均值方差归一化文件
+    /// - `config`: FunASR 模型配置文件
+    /// - `default`: 内置的前端配置
+    pub fn load(
+        am_mvn: impl AsRef<Path>,
+        config: impl AsRef<Path>,
+        default: &WavFrontend,
+    ) -> Result<Self> {
+        let mut frontend = default.clone();
+        if am_mvn.as_ref().exists() {
+            frontend.cmvn = CMVN::from_am_mvn(am_mvn)?;
+        }
+        if config.as_ref().exists() {
+            let content = std::fs::read_to_string(config.as_ref())?;
+            let config: ModelConfig = serde_yaml::from_str(&content)?;
+            frontend.apply_config(config.frontend_conf)?;
+        }
+        let feature_size = frontend.fbank_options.feature_dim() * frontend.lfr_m;
+        if frontend.cmvn.means.len() != feature_size {
+            return Err(anyhow!(
+                "CMVN 维度 {} 与特征维度 {} 不一致",
+                frontend.cmvn.means.len(),
+                feature_size
+            ));
+        }
+        Ok(frontend)
+    }
+
+    /// 应用 `config.yaml` 中的前端配置
+    fn apply_config(&mut self, conf: FrontendConf) -> Result<()> {
+        let options = &mut self.fbank_options;
+        if let Some(window) = conf.window {
+            options.window_type = match window.as_str() {
+                "hamming" => WindowType::Hamming,
+                "povey" => WindowType::Povey,
+                "hann" | "hanning" => WindowType::Hann,
+                "rectangular" => WindowType::Rectangular,
+                _ => return Err(anyhow!("不支持的窗函数: {}", window)),
+            };
+        }
+        // 音频统一重采样至 SAMPLE_RATE，不支持其他采样率的模型
+        if let Some(fs) = conf.fs {
+            if fs != SAMPLE_RATE {
+                return Err(anyhow!(
+                    "不支持的采样率: {}，模型需要 {}Hz",
+                    fs,
+                    SAMPLE_RATE
+                ));
+            }
+        }
+        options.mel_bins = conf.n_mels.unwrap_or(options.mel_bins);
+        options.frame_length_ms = conf.frame_length.unwrap_or(options.frame_length_ms);
+        options.frame_shift_ms = conf.frame_shift.unwrap_or(options.frame_shift_ms);
+        options.dither = conf.dither.unwrap_or(options.dither);
+        options.snip_edges = conf.snip_edges.unwrap_or(options.snip_edges);
+        self.lfr_m = conf.lfr_m.unwrap_or(self.lfr_m);
+        self.lfr_n = conf.lfr_n.unwrap_or(self.lfr_n);
+        Ok(())
+    }
+
+    /// 模型训练时使用的 fbank 配置
+    pub fn fbank_options(&self) -> &FbankOptions {
+        &self.fbank_options
+    }
+
+    /// LFR 步长，每行特征对应的音频帧数
+    pub fn lfr_n(&self) -> usize {
+        self.lfr_n
+    }
     /// 提取 特征
     /// 参数:
-    ///     features: fbank 提取的特征
+    ///     frames: fbank 提取的音频帧
     /// 返回：
-    ///     features：LFR处理+倒谱均值和方差归一化 后的特征向量
-    ///     reserve_frames：剩余的帧
-    pub fn extract_features(&self, frames: &Vec<Frame>) -> (Array2<f32>, Vec<Frame>) {
-        let (features, frames) = self.apply_lfr(frames);
-        (self.apply_cmvn(features), frames)
+    ///     LFR处理+倒谱均值和方差归一化 后的特征向量，不足一个 LFR 窗口的剩余帧被忽略
+    pub fn extract_features(&self, frames: &[Arc<Frame>]) -> Array2<f32> {
+        self.extract_features_with(frames, &self.cmvn)
     }
 
-    /// LFR处理
-    fn apply_lfr(&self, frames: &Vec<Frame>) -> (Array2<f32>, Vec<Frame>) {
+    /// 使用指定的 CMVN 提取特征
+    fn extract_features_with(&self, frames: &[Arc<Frame>], cmvn: &CMVN) -> Array2<f32> {
         let frames_count = frames.len();
         // 计算LFR处理后的帧数
         // 公式：(总帧数 - 上下文帧数) / 步长，向下取整
         let t_lfr = ((frames_count as f32 - self.lfr_m as f32) / self.lfr_n as f32) as usize;
-        // 计算LFR处理后每帧的长度
-        let lfr_len = MEL_BINS * self.lfr_m;
-        let mut lfr_input: Array2<f32> = Array2::zeros((t_lfr, lfr_len));
-        for t in 0..t_lfr {
+        let mut features = Array2::zeros((t_lfr, self.lfr_len(frames)));
+        for (t, row) in features.axis_iter_mut(Axis(0)).enumerate() {
             let start = t * self.lfr_n;
-            let end = start + self.lfr_m;
-            let features_cloned = frames[start..end]
-                .iter()
-                .map(|frame| frame.feature.view())
-                .collect::<Vec<ArrayView1<f32>>>();
-            let features = concatenate(Axis(0), &features_cloned).unwrap();
-            lfr_input.slice_mut(s![t, ..]).assign(&features);
-        }
-        // 将剩余特征保存
-        let frames = frames[t_lfr * self.lfr_n..].to_vec();
-        (lfr_input, frames)
-    }
-
-    /// 应用CMVN 倒谱均值和方差归一化
-    fn apply_cmvn(&self, features: Array2<f32>) -> Array2<f32> {
-        let (frames, feature) = features.dim();
-        let mut new_features: Array2<f32> = Array2::zeros((frames, feature));
-        for t in 0..frames {
-            let mut new_frame = features.slice(s![t, ..]).to_owned();
-            for i in 0..feature {
-                // 应用CMVN变换：(特征值 + 均值) * 方差倒数
-                new_frame[i] = (new_frame[i] + self.cmvn.means[i]) * self.cmvn.vars[i];
-            }
-            new_features.slice_mut(s![t, ..]).assign(&new_frame);
+            self.lfr_row(&frames[start..start + self.lfr_m], cmvn, row);
+        }
+        features
+    }
+
+    /// LFR处理后每帧的长度，由 fbank 的梅尔滤波器数量决定
+    fn lfr_len(&self, frames: &[Arc<Frame>]) -> usize {
+        frames.first().map_or(0, |frame| frame.feature.len()) * self.lfr_m
+    }
+
+    /// 拼接 `lfr_m` 个音频帧为一行特征，并应用CMVN 倒谱均值和方差归一化
+    fn lfr_row(&self, frames: &[Arc<Frame>], cmvn: &CMVN, mut row: ArrayViewMut1<f32>) {
+        let mut offset = 0;
+        for frame in frames {
+            let length = frame.feature.len();
+            row.slice_mut(s![offset..offset + length])
+                .assign(&frame.feature);
+            offset += length;
         }
-        new_features
+        for ((x, &mean), &var) in row.iter_mut().zip(&cmvn.means).zip(&cmvn.vars) {
+            // 应用CMVN变换：(特征值 + 均值) * 方差倒数
+            *x = (*x + mean) * var;
+        }
+    }
+}
+
+/// 流式前端
+/// 保存 LFR 所需的上下文帧，音频帧到达时只计算新增的特征行，
+/// 音频帧以 `Arc` 共享，不复制特征
+/// 使用在线 CMVN 时同时保存会话的统计量
+pub struct StreamingFrontend {
+    frontend: WavFrontend,
+    /// 尚未完成 LFR 拼接的音频帧
+    context: VecDeque<Arc<Frame>>,
+    /// 在线 CMVN 的统计量，使用全局 CMVN 时为 None
+    online_cmvn: Option<OnlineCmvn>,
+}
+
+impl StreamingFrontend {
+    pub fn new(frontend: &WavFrontend, cmvn_mode: CmvnMode) -> Self {
+        let online_cmvn = match cmvn_mode {
+            CmvnMode::Global => None,
+            CmvnMode::Online(options) => Some(OnlineCmvn::new(&frontend.cmvn, options)),
+        };
+        Self {
+            frontend: frontend.clone(),
+            context: VecDeque::new(),
+            online_cmvn,
+        }
+    }
+
+    /// 输入新的音频帧
+    /// 与 `WavFrontend::extract_features` 一致，上下文中的帧数达到一个 LFR 窗口加一个步长时输出一行特征
+    /// 使用在线 CMVN 时先以新的音频帧更新统计量
+    /// # 返回值
+    /// - 新增的特征行
+    /// - 每行特征对应的第一个音频帧
+    pub fn accept(&mut self, frames: &[Arc<Frame>]) -> (Array2<f32>, Vec<Arc<Frame>>) {
+        self.observe(frames);
+        self.context.extend(frames.iter().cloned());
+        let (lfr_m, lfr_n) = (self.frontend.lfr_m, self.frontend.lfr_n);
+        let rows = self.context.len().saturating_sub(lfr_m) / lfr_n;
+        let cmvn = current_cmvn(&self.frontend, &self.online_cmvn);
+        let context = self.context.make_contiguous();
+        let mut features = Array2::zeros((rows, self.frontend.lfr_len(context)));
+        let mut row_frames = Vec::with_capacity(rows);
+        for (t, row) in features.axis_iter_mut(Axis(0)).enumerate() {
+            let start = t * lfr_n;
+            self.frontend
+                .lfr_row(&context[start..start + lfr_m], &cmvn, row);
+            row_frames.push(Arc::clone(&context[start]));
+        }
+        self.context.drain(..rows * lfr_n);
+        (features, row_frames)
+    }
+
+    /// 只以音频帧更新在线 CMVN 的统计量，不输出特征
+    pub fn observe(&mut self, frames: &[Arc<Frame>]) {
+        if let Some(online_cmvn) = &mut self.online_cmvn {
+            online_cmvn.update(frames);
+        }
+    }
+
+    /// 使用当前的 CMVN 一次性提取完整音频帧的特征，不影响流式的上下文与统计量
+    pub fn extract_features(&self, frames: &[Arc<Frame>]) -> Array2<f32> {
+        let cmvn = current_cmvn(&self.frontend, &self.online_cmvn);
+        self.frontend.extract_features_with(frames, &cmvn)
+    }
+
+    /// 清空上下文，开始新的音频流
+    /// 在线 CMVN 的统计量属于整个会话，不会清空
+    pub fn reset(&mut self) {
+        self.context.clear();
+    }
+}
+
+/// 流式前端当前使用的 CMVN
+fn current_cmvn<'a>(frontend: &'a WavFrontend, online_cmvn: &Option<OnlineCmvn>) -> Cow<'a, CMVN> {
+    match online_cmvn {
+        None => Cow::Borrowed(&frontend.cmvn),
+        Some(online_cmvn) => Cow::Owned(online_cmvn.cmvn(&frontend.cmvn)),
     }
 }
@@ -0,0 +1,600 @@
+/// 基于规则的逆文本正则化，将中文与英文的数字读法转为阿拉伯数字
+/// 如 `三百五十万元` 转为 `350万元`，`百分之十二点五` 转为 `12.5%`，`twenty five dollars` 转为 `$25`
+pub fn inverse_normalize(text: &str) -> String {
+    normalize_english(&normalize_chinese(text))
+}
+
+/// 流式文本的逆文本正则化
+/// 文本末尾的数字可能在下一段文本中继续，暂不输出，直到数字结束
+#[derive(Default)]
+pub struct StreamingItn {
+    /// 尚未输出的文本
+    pending: String,
+}
+
+impl StreamingItn {
+    /// 输入新增的文本，返回可以输出的正则化文本
+    pub fn accept(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+        let stable = stable_len(&self.pending);
+        let stable: String = self.pending.drain(..stable).collect();
+        inverse_normalize(&stable)
+    }
+
+    /// 输出全部未输出的文本
+    pub fn finish(&mut self) -> String {
+        inverse_normalize(&std::mem::take(&mut self.pending))
+    }
+
+    /// 清空未输出的文本，开始新的语音段
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// 文本中不以可能继续的数字结尾的部分的长度，单位：字节
+fn stable_len(text: &str) -> usize {
+    // 中文数字以及可能组成数字读法的字
+    let chinese = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| {
+            chinese_digit(*c).is_some()
+                || chinese_unit(*c).is_some()
+                || matches!(c, '点' | '第' | '负' | '分' | '之')
+        })
+        .last()
+        .map(|(index, _)| index);
+    if let Some(index) = chinese {
+        return index;
+    }
+    // 英文数字以空格分隔，末尾的数字单词及可能连接数字的单词
+    let mut stable = text.len();
+    let mut end = text.len();
+    for word in text.rsplit(' ') {
+        let word_start = end - word.len();
+        let word = word.to_lowercase();
+        if english_number(&word).is_none() && !matches!(word.as_str(), "point" | "and" | "") {
+            break;
+        }
+        stable = word_start;
+        end = word_start.saturating_sub(1);
+    }
+    stable
+}
+
+fn chinese_digit(c: char) -> Option<u64> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' | '幺' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    }
+}
+
+fn chinese_unit(c: char) -> Option<u64> {
+    match c {
+        '十' => Some(10),
+        '百' => Some(100),
+        '千' => Some(1000),
+        '万' => Some(10_000),
+        '亿' => Some(100_000_000),
+        _ => None,
+    }
+}
+
+/// 单个数字后接这些字时转为阿拉伯数字，如 `三月`、`五元`
+const SINGLE_DIGIT_SUFFIXES: &[char] = &['月', '日', '号', '元', '岁', '倍', '季'];
+/// 单独的 `十` 后接这些量词时转为阿拉伯数字，如 `十个`、`十年`
+const MEASURE_WORDS: &[char] = &[
+    '个', '年', '天', '次', '块', '人', '秒', '点', '位', '名', '米',
+];
+
+/// 从 `start` 开始解析的中文数字
+struct ChineseNumber {
+    /// 阿拉伯数字
+    text: String,
+    /// 占用的字数
+    len: usize,
+    /// 是否带有十、百、千等单位
+    has_unit: bool,
+    /// 是否带有小数部分
+    decimal: bool,
+}
+
+/// 从 `start` 开始的连续中文数字与单位的字数
+fn chinese_run_len(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .take_while(|&&c| chinese_digit(c).is_some() || chinese_unit(c).is_some())
+        .count()
+}
+
+/// 解析从 `start` 开始的中文数字，不是数字或不是合法的读法时返回 None
+fn parse_chinese_number(chars: &[char], start: usize) -> Option<ChineseNumber> {
+    let run_len = chinese_run_len(chars, start);
+    if run_len == 0 {
+        return None;
+    }
+    let run = &chars[start..start + run_len];
+    let has_unit = run.iter().any(|&c| chinese_unit(c).is_some());
+    let mut text = if has_unit {
+        chinese_with_units(run)?
+    } else {
+        run.iter()
+            .filter_map(|&c| chinese_digit(c))
+            .map(|d| d.to_string())
+            .collect()
+    };
+    let mut len = run_len;
+
+    // 小数部分只能是逐位读出的数字，如 `三点一四`
+    let mut decimal = false;
+    if chars.get(start + len) == Some(&'点') && !text.ends_with(['万', '亿']) {
+        let fraction: Vec<u64> = chars[start + len + 1..]
+            .iter()
+            .map_while(|&c| chinese_digit(c))
+            .collect();
+        let next = chars.get(start + len + 1 + fraction.len());
+        if !fraction.is_empty() && next.and_then(|&c| chinese_unit(c)).is_none() {
+            text.push('.');
+            text.extend(fraction.iter().map(|d| d.to_string()));
+            len += 1 + fraction.len();
+            decimal = true;
+        }
+    }
+    Some(ChineseNumber {
+        text,
+        len,
+        has_unit,
+        decimal,
+    })
+}
+
+/// 转换带单位的中文数字，如 `一千零五` 转为 `1005`
+/// 以 `万`、`亿` 结尾时保留该单位，如 `三百万` 转为 `300万`
+fn chinese_with_units(run: &[char]) -> Option<String> {
+    if let Some((&last, prefix)) = run.split_last() {
+        let large_unit = matches!(last, '万' | '亿');
+        if large_unit && !prefix.iter().any(|c| matches!(c, '万' | '亿')) {
+            let value = chinese_value(prefix).filter(|&value| value > 0)?;
+            return Some(format!("{}{}", value, last));
+        }
+    }
+    chinese_value(run).map(|value| value.to_string())
+}
+
+/// 计算带单位的中文数字的值，支持 `三万五`、`两百五` 等口语读法
+fn chinese_value(run: &[char]) -> Option<u64> {
+    let mut yi_part: u64 = 0;
+    let mut wan_part: u64 = 0;
+    let mut section: u64 = 0;
+    let mut current: Option<u64> = None;
+    // 上一个单位，口语读法中末尾的数字为该单位的十分之一
+    let mut last_unit: u64 = 1;
+    let mut after_zero = false;
+    for &c in run {
+        if let Some(digit) = chinese_digit(c) {
+            if current.is_some() {
+                // 连续的数字不是带单位的读法
+                return None;
+            }
+            if digit == 0 {
+                after_zero = true;
+            } else {
+                current = Some(digit);
+            }
+            continue;
+        }
+        let unit = chinese_unit(c)?;
+        match unit {
+            10 | 100 | 1000 => {
+                let digit = match current.take() {
+                    Some(digit) => digit,
+                    None if unit == 10 => 1,
+                    None => return None,
+                };
+                section += digit * unit;
+            }
+            10_000 => {
+                let value = section + current.take().unwrap_or(0);
+                if value == 0 || wan_part != 0 {
+                    return None;
+                }
+                wan_part = value * unit;
+                section = 0;
+            }
+            _ => {
+                let value = wan_part + section + current.take().unwrap_or(0);
+                if value == 0 || yi_part != 0 {
+                    return None;
+                }
+                yi_part = value * unit;
+                wan_part = 0;
+                section = 0;
+            }
+        }
+        last_unit = unit;
+        after_zero = false;
+    }
+    if let Some(digit) = current {
+        section += if after_zero || last_unit < 100 {
+            digit
+        } else {
+            digit * last_unit / 10
+        };
+    }
+    Some(yi_part + wan_part + section)
+}
+
+/// 中文数字的逆文本正则化
+fn normalize_chinese(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        // 百分数，如 `百分之五` 转为 `5%`
+        if chars[i..].starts_with(&['百', '分', '之']) {
+            if let Some(number) = parse_chinese_number(&chars, i + 3) {
+                output.push_str(&number.text);
+                output.push('%');
+                i += 3 + number.len;
+                continue;
+            }
+        }
+        // 负数，如 `负三点五` 转为 `-3.5`
+        if chars[i] == '负' {
+            if let Some(number) = parse_chinese_number(&chars, i + 1) {
+                output.push('-');
+                output.push_str(&number.text);
+                i += 1 + number.len;
+                continue;
+            }
+        }
+        match parse_chinese_number(&chars, i) {
+            Some(number) => {
+                if should_convert(&chars, i, &number) {
+                    output.push_str(&number.text);
+                } else {
+                    output.extend(&chars[i..i + number.len]);
+                }
+                i += number.len;
+            }
+            None => {
+                // 不是合法读法的数字整体保留，如 `四五十`、`万一`
+                let len = chinese_run_len(&chars, i).max(1);
+                output.extend(&chars[i..i + len]);
+                i += len;
+            }
+        }
+    }
+    output
+}
+
+/// 判断中文数字是否需要转换，避免转换 `一个`、`十分重要`、`三四个` 等词语中的数字
+fn should_convert(chars: &[char], start: usize, number: &ChineseNumber) -> bool {
+    if number.decimal {
+        return true;
+    }
+    let next = chars.get(start + number.len).copied();
+    let previous = start.checked_sub(1).map(|i| chars[i]);
+    if number.len == 1 && chars[start] == '十' {
+        // 单独的 `十` 常见于 `十字路口`、`十足`、`十全十美` 等词语，只在 `第` 之后或量词之前转换
+        // `十分` 表示程度，`十分钟` 表示时间
+        if next == Some('分') {
+            return chars.get(start + 2) == Some(&'钟');
+        }
+        return previous == Some('第')
+            || next
+                .is_some_and(|c| SINGLE_DIGIT_SUFFIXES.contains(&c) || MEASURE_WORDS.contains(&c));
+    }
+    if number.has_unit {
+        return true;
+    }
+    let digits = &chars[start..start + number.len];
+    if digits.len() >= 3 || (digits.len() == 2 && digits.iter().any(|&c| matches!(c, '零' | '〇')))
+    {
+        return true;
+    }
+    previous == Some('第')
+        || next == Some('年')
+        || (digits.len() == 1 && next.is_some_and(|c| SINGLE_DIGIT_SUFFIXES.contains(&c)))
+}
+
+fn english_number(word: &str) -> Option<u64> {
+    let value = match word {
+        "zero" => 0,
+        "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        "thirteen" => 13,
+        "fourteen" => 14,
+        "fifteen" => 15,
+        "sixteen" => 16,
+        "seventeen" => 17,
+        "eighteen" => 18,
+        "nineteen" => 19,
+        "twenty" => 20,
+        "thirty" => 30,
+        "forty" => 40,
+        "fifty" => 50,
+        "sixty" => 60,
+        "seventy" => 70,
+        "eighty" => 80,
+        "ninety" => 90,
+        "hundred" => 100,
+        "thousand" => 1_000,
+        "million" => 1_000_000,
+        "billion" => 1_000_000_000,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// 拆分单词与其后的标点，如 `five,` 拆分为 `five` 与 `,`
+fn split_punctuation(word: &str) -> (&str, &str) {
+    let end = word
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .len();
+    word.split_at(end)
+}
+
+/// 从 `start` 开始解析的英文数字
+struct EnglishNumber {
+    text: String,
+    /// 占用的单词数
+    len: usize,
+    /// 是否可以转换，一到九单独出现时保留单词
+    convert: bool,
+}
+
+/// 解析从 `start` 开始的英文数字，无法解析时返回 None
+fn parse_english_number(words: &[&str], start: usize) -> Option<EnglishNumber> {
+    let mut total: u64 = 0;
+    let mut current: u64 = 0;
+    // 上一个单词是否为个位数或十几，之后不能再接个位数或整十数
+    let mut previous_ones = false;
+    let mut previous_tens = false;
+    // 上一个单词是否为 hundred、thousand 等，之后可以接 and
+    let mut previous_scale = false;
+    let mut len = 0;
+    let mut count = 0;
+    while let Some(word) = words.get(start + len) {
+        let (core, punctuation) = split_punctuation(word);
+        let core = core.to_lowercase();
+        if core == "and" && previous_scale && punctuation.is_empty() {
+            // `one hundred and five` 中的 and
+            let next = words.get(start + len + 1).map(|w| split_punctuation(w).0);
+            if next
+                .and_then(|w| english_number(&w.to_lowercase()))
+                .is_some_and(|v| v < 100)
+            {
+                len += 1;
+                continue;
+            }
+            break;
+        }
+        let Some(value) = english_number(&core) else {
+            break;
+        };
+        match value {
+            0..=19 => {
+                if previous_ones {
+                    break;
+                }
+                current += value;
+                previous_ones = true;
+                previous_scale = false;
+            }
+            20..=90 => {
+                if previous_ones || previous_tens {
+                    break;
+                }
+                current += value;
+                previous_tens = true;
+                previous_scale = false;
+            }
+            100 => {
+                current = current.max(1) * 100;
+                previous_ones = false;
+                previous_tens = false;
+                previous_scale = true;
+            }
+            scale => {
+                total += current.max(1) * scale;
+                current = 0;
+                previous_ones = false;
+                previous_tens = false;
+                previous_scale = true;
+            }
+        }
+        len += 1;
+        count += 1;
+        if !punctuation.is_empty() {
+            break;
+        }
+    }
+    if count == 0 {
+        return None;
+    }
+    let value = total + current;
+    let mut text = value.to_string();
+    let last_punctuation = split_punctuation(words[start + len - 1]).1;
+
+    // 小数部分逐位读出，如 `three point one four`
+    let mut decimal = false;
+    if last_punctuation.is_empty()
+        && words
+            .get(start + len)
+            .is_some_and(|w| w.eq_ignore_ascii_case("point"))
+    {
+        let fraction: Vec<(u64, &str)> = words[start + len + 1..]
+            .iter()
+            .map(|w| split_punctuation(w))
+            .map_while(|(core, punctuation)| {
+                english_number(&core.to_lowercase())
+                    .filter(|&v| v < 10)
+                    .map(|v| (v, punctuation))
+            })
+            .collect();
+        // 遇到标点时小数结束
+        let fraction_len = fraction
+            .iter()
+            .position(|(_, punctuation)| !punctuation.is_empty())
+            .map_or(fraction.len(), |i| i + 1);
+        if fraction_len > 0 {
+            text.push('.');
+            text.extend(fraction[..fraction_len].iter().map(|(v, _)| v.to_string()));
+            len += 1 + fraction_len;
+            decimal = true;
+        }
+    }
+    Some(EnglishNumber {
+        text,
+        len,
+        convert: decimal || count > 1 || value >= 10,
+    })
+}
+
+/// 英文数字的逆文本正则化
+fn normalize_english(text: &str) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut output: Vec<String> = Vec::with_capacity(words.len());
+    let mut i = 0;
+    while i < words.len() {
+        let Some(number) = parse_english_number(&words, i) else {
+            output.push(words[i].to_string());
+            i += 1;
+            continue;
+        };
+        let last = words[i + number.len - 1];
+        let (_, punctuation) = split_punctuation(last);
+        // 数字之后的百分号与货币单位
+        let next = words
+            .get(i + number.len)
+            .copied()
+            .filter(|_| punctuation.is_empty());
+        let (next_core, next_punctuation) = next.map(split_punctuation).unwrap_or_default();
+        match next_core.to_lowercase().as_str() {
+            "percent" => {
+                output.push(format!("{}%{}", number.text, next_punctuation));
+                i += number.len + 1;
+            }
+            "dollar" | "dollars" => {
+                output.push(format!("${}{}", number.text, next_punctuation));
+                i += number.len + 1;
+            }
+            _ if number.convert => {
+                output.push(format!("{}{}", number.text, punctuation));
+                i += number.len;
+            }
+            _ => {
+                output.extend(words[i..i + number.len].iter().map(|w| w.to_string()));
+                i += number.len;
+            }
+        }
+    }
+    output.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_chinese_numbers() {
+        let cases = [
+            ("今年营收三百五十万元", "今年营收350万元"),
+            ("同比增长百分之十二点五", "同比增长12.5%"),
+            ("二零二四年三月五日", "2024年3月5日"),
+            ("一千零五", "1005"),
+            ("三百万", "300万"),
+            ("一千二百三十四万五千六百七十八", "12345678"),
+            ("一百零五", "105"),
+            ("三万五", "35000"),
+            ("两百五十块", "250块"),
+            ("三月", "3月"),
+            ("五元", "5元"),
+            ("第三季度", "第3季度"),
+            ("负三点五", "-3.5"),
+            ("开会十分钟", "开会10分钟"),
+            ("等了十年", "等了10年"),
+            ("第十名", "第10名"),
+            ("二十个人", "20个人"),
+            ("十五", "15"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(inverse_normalize(input), expected, "输入: {}", input);
+        }
+    }
+
+    #[test]
+    fn keeps_chinese_words() {
+        let cases = [
+            "一个",
+            "这个十分重要",
+            "三四个",
+            "四五十个人",
+            "万一千万不要",
+            "一些人统一行动",
+            "零食",
+            "一点点",
+            "三点开会",
+            "十字路口",
+            "信心十足",
+            "十全十美",
+        ];
+        for input in cases {
+            assert_eq!(inverse_normalize(input), input, "输入: {}", input);
+        }
+    }
+
+    #[test]
+    fn converts_english_numbers() {
+        let cases = [
+            ("twenty five dollars", "$25"),
+            (
+                "twenty five percent of one hundred and five dollars",
+                "25% of $105",
+            ),
+            (
+                "one of them, three point one four, forty two.",
+                "one of them, 3.14, 42.",
+            ),
+            ("five and six", "five and six"),
+            (
+                "it costs twelve thousand five hundred dollars",
+                "it costs $12500",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(inverse_normalize(input), expected, "输入: {}", input);
+        }
+    }
+
+    #[test]
+    fn streaming_waits_for_numbers_to_finish() {
+        let mut itn = StreamingItn::default();
+        let mut output = itn.accept("营收三百");
+        output += &itn.accept("五十万元，");
+        output += &itn.accept("增长百分之十");
+        output += &itn.finish();
+        assert_eq!(output, "营收350万元，增长10%");
+    }
+}
@@ -1,14 +1,30 @@
+mod archive;
+mod audio_file;
+mod channel;
 pub mod constant;
+mod device_config;
 pub mod download;
 mod e2e_vad;
 mod fbank;
 mod frontend;
+mod itn;
 mod recorder;
+mod resampler;
+mod ring_buffer;
 mod session;
+mod source;
 mod token;
-pub use e2e_vad::{E2EVadModel, PointType, Segment};
-pub use fbank::{fbank, Frame};
-pub use frontend::{WavFrontend, CMVN};
-pub use recorder::{default_device, devices, hosts, Recorder};
+pub use archive::{read_archived_segment, ArchiveMode, AudioArchive};
+pub use audio_file::{read_audio_file, read_audio_file_channels};
+pub use channel::{downmix, split_channels, ChannelMode};
+pub use device_config::{best_input_config, device_configs, input_channels, DeviceConfigs};
+pub use e2e_vad::{E2EVadModel, PointType, Segment, VadConfig, VadSensitivity};
+pub use fbank::{Fbank, Frame};
+pub use frontend::{CmvnConfig, CmvnMode, StreamingFrontend, WavFrontend, CMVN};
+pub use itn::StreamingItn;
+pub use recorder::{default_device, devices, hosts, OverflowPolicy, Recorder};
+pub use resampler::{resample, Resampler};
+pub use ring_buffer::{ring_buffer, Consumer, Producer, ReadNotifier};
 pub use session::OrtInferSession;
-pub use token::{read_token, TokenIdConverter};
+pub use source::{AudioSource, FileSource, PcmFormat, Signal, SignalSource, StdinSource};
+pub use token::{detokenize, read_token, Detokenizer, TokenIdConverter};
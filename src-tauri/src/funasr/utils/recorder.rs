@@ -1,11 +1,34 @@
 use crate::funasr::utils::constant::SAMPLE_RATE;
+use crate::funasr::utils::{
+    best_input_config, downmix, ring_buffer, split_channels, AudioSource, ChannelMode, Consumer,
+    Producer, ReadNotifier, Resampler,
+};
+use anyhow::{anyhow, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Devices, DevicesError, HostId, InputDevices, SampleFormat};
+use cpal::{Device, Devices, DevicesError, HostId, InputDevices, Sample, SampleFormat, I24};
+use log::{error, info};
 use num_traits::{Bounded, FromPrimitive, NumCast};
+use serde::{Deserialize, Serialize};
 use std::any::TypeId;
 use std::sync::{Arc, Mutex};
 
-const MAX_QUEUE_SIZE: usize = 16000 * 300; // 约5分钟的16kHz音频
+const MAX_QUEUE_SIZE: usize = 16000 * 300; // 每个声道约5分钟的16kHz音频
+// 丢弃旧数据时保留10秒空间，识别短暂阻塞时音频回调仍可写入
+const DROP_OLDEST_HEADROOM: usize = 16000 * 10;
+const LAGGING_START: usize = 16000; // 积压超过1秒时开始跳过实时识别
+const LAGGING_END: usize = 3200; // 积压少于200ms时恢复实时识别
+
+/// 识别速度跟不上录音、音频队列积压时的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    /// 队列接近满时丢弃最早的音频，识别跳到最新的内容
+    #[default]
+    DropOldest,
+    /// 队列满时丢弃新录制的音频，已缓存的音频保持完整
+    DropNewest,
+    /// 积压时暂停 paraformer 实时识别直到队列追上，队列满时丢弃新录制的音频
+    SkipOnline,
+}
 
 /// 获取可用的音频主机列表
 ///
@@ -22,7 +45,7 @@ pub fn hosts() -> Vec<HostId> {
 ///
 /// # 返回值
 /// 返回包含输入设备的 Result，如果获取失败则返回 DevicesError 错误
-pub fn devices(host: HostId) -> Result<InputDevices<Devices>, DevicesError> {
+pub fn devices(host: HostId) -> std::result::Result<InputDevices<Devices>, DevicesError> {
     let host = cpal::host_from_id(host).expect("获取主机失败");
     host.input_devices()
 }
@@ -36,69 +59,134 @@ pub fn default_device() -> Option<Device> {
 }
 
 pub struct Recorder {
-    pub samples_queue: Arc<Mutex<Vec<f32>>>, // 音频采样队列
+    samples_queue: Consumer, // 每个输出声道的音频采样队列，由音频回调写入
+    overflow_policy: OverflowPolicy, // 队列积压时的处理方式
+    dropped_oldest: usize,           // 按 DropOldest 丢弃的采样总数
+    reported_dropped: usize,         // 已通过 take_dropped 报告的采样数
+    lagging: bool,                   // 是否正在跳过实时识别
+    device_name: Option<String>,     // 输入设备名称，没有可用设备时为 None
+    stream_error: Arc<Mutex<Option<String>>>, // 音频流的错误，由音频流的错误回调写入
+    error_reported: bool,            // 错误是否已通过 take_error 报告
     #[allow(dead_code)] // 保证音频流存活，不然不会读取设备输入
-    stream: cpal::Stream,                    // 音频流
+    stream: Option<cpal::Stream>,            // 音频流，没有可用设备时为 None
 }
 
 
 impl Recorder {
     /// 创建一个新的Recorder实例，使用指定采样率
-    pub fn new(device: Device) -> Self {
-        Self::new_with_max_duration(device, SAMPLE_RATE as u32) // 默认最大5分钟
+    /// # 参数
+    /// - `device`: 输入设备
+    /// - `channel_mode`: 多声道音频的处理方式
+    /// - `overflow_policy`: 音频队列积压时的处理方式
+    pub fn new(
+        device: Device,
+        channel_mode: ChannelMode,
+        overflow_policy: OverflowPolicy,
+    ) -> Result<Self> {
+        let mut recorder =
+            Self::new_with_max_duration(device, SAMPLE_RATE as u32, channel_mode)?; // 默认最大5分钟
+        recorder.set_overflow_policy(overflow_policy);
+        Ok(recorder)
+    }
+
+    /// 创建一个没有输入设备的Recorder实例，不会产生任何音频数据
+    pub fn unavailable() -> Self {
+        let (_, samples_queue) = ring_buffer(1, 1);
+        Self {
+            samples_queue,
+            overflow_policy: OverflowPolicy::default(),
+            dropped_oldest: 0,
+            reported_dropped: 0,
+            lagging: false,
+            device_name: None,
+            stream_error: Arc::new(Mutex::new(None)),
+            error_reported: false,
+            stream: None,
+        }
     }
     /// 创建一个新的Recorder实例，指定最大录制时长
     pub fn new_with_max_duration(
         device: Device,
         sample_rate: u32,
-    ) -> Self {
+        channel_mode: ChannelMode,
+    ) -> Result<Self> {
+        let device_name = device.name()?;
+        // 音频流出错（如设备被拔出）时记录错误，由识别流程和设备监听读取
+        let stream_error = Arc::new(Mutex::new(None));
         // 注意不要删除，宏里面要使用的
         #[allow(unused)]
-        let err_fn = |err| {
-            eprintln!("An error occurred on the output stream: {}", err);
+        let err_fn = {
+            let stream_error = Arc::clone(&stream_error);
+            let device_name = device_name.clone();
+            move |err: cpal::StreamError| {
+                error!("输入设备 {} 音频流出错: {}", device_name, err);
+                *stream_error.lock().expect("获取锁失败") = Some(err.to_string());
+            }
         };
 
-        let config = device
-            .default_input_config()
-            .expect("Failed to get default input config");
+        let config = best_input_config(&device, sample_rate, channel_mode)?;
+        channel_mode.validate(config.channels() as usize)?;
+        info!(
+            "输入设备 {} 使用配置: {} 声道 {}Hz {}",
+            device_name,
+            config.channels(),
+            config.sample_rate().0,
+            config.sample_format()
+        );
         // 注意不要删除，宏里面要使用的
         #[allow(unused)]
         let source_sample_rate = config.sample_rate().0;
-        let samples_queue = Arc::new(Mutex::new(Vec::new()));
+        // 注意不要删除，宏里面要使用的
+        #[allow(unused)]
+        let channels = config.channels() as usize;
+        let output_channels = channel_mode.output_channels(channels);
+        let (producer, samples_queue) = ring_buffer(output_channels, MAX_QUEUE_SIZE);
         #[allow(unused)]
         let target_sample_rate = sample_rate;
 
         // 宏来减少重复代码
         macro_rules! build_stream {
             ($sample_type:ty) => {
+                build_stream!($sample_type, process_samples)
+            };
+            ($sample_type:ty, $process:expr) => {
                 device
                     .build_input_stream(
                         &config.into(),
                         {
-                            let samples_queue = Arc::clone(&samples_queue);
+                            let mut producer = producer;
+                            // 重采样器保存在回调中，保证块之间的滤波器状态连续
+                            let mut resamplers: Vec<Resampler> = (0..output_channels)
+                                .map(|_| Resampler::new(source_sample_rate, target_sample_rate))
+                                .collect::<Result<_>>()?;
                             move |data: &[$sample_type], _: &_| {
-                                process_samples(
+                                $process(
                                     data,
-                                    source_sample_rate,
-                                    target_sample_rate,
-                                    &samples_queue,
+                                    channels,
+                                    channel_mode,
+                                    &mut resamplers,
+                                    &mut producer,
                                 );
                             }
                         },
                         err_fn,
                         None,
                     )
-                    .expect(concat!(
-                        "Failed to build input stream for ",
-                        stringify!($sample_type)
-                    ))
+                    .map_err(|e| {
+                        anyhow!(
+                            "创建 {} 格式的音频流失败: {}",
+                            stringify!($sample_type),
+                            e
+                        )
+                    })?
             };
         }
 
         let stream:cpal::Stream = match config.sample_format() {
             SampleFormat::I8 => build_stream!(i8),
             SampleFormat::I16 => build_stream!(i16),
-            SampleFormat::I32 | SampleFormat::I24 => build_stream!(i32),
+            SampleFormat::I24 => build_stream!(I24, process_i24_samples),
+            SampleFormat::I32 => build_stream!(i32),
             SampleFormat::I64 => build_stream!(i64),
             SampleFormat::U8 => build_stream!(u8),
             SampleFormat::U16 => build_stream!(u16),
@@ -106,39 +194,121 @@ impl Recorder {
             SampleFormat::U64 => build_stream!(u64),
             SampleFormat::F32 => build_stream!(f32),
             SampleFormat::F64 => build_stream!(f64),
-            _ => panic!("Unsupported sample format"),
+            sample_format => return Err(anyhow!("不支持的采样格式: {}", sample_format)),
         };
-        stream.play().expect("开始录制失败");
+        stream.play()?;
 
-        Self {
+        Ok(Self {
             samples_queue,
-            stream,
-        }
+            overflow_policy: OverflowPolicy::default(),
+            dropped_oldest: 0,
+            reported_dropped: 0,
+            lagging: false,
+            device_name: Some(device_name),
+            stream_error,
+            error_reported: false,
+            stream: Some(stream),
+        })
+    }
+
+    /// 输入设备名称，没有可用设备时返回 None
+    pub fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
+
+    /// 音频流是否正常运行
+    pub fn is_available(&self) -> bool {
+        self.stream.is_some() && self.stream_error.lock().expect("获取锁失败").is_none()
     }
 
-    pub fn pop_head_sample(&mut self, chunk_size: usize) -> Option<Vec<f32>> {
-        let mut queue = self.samples_queue.lock().expect("获取锁失败");
-        if queue.is_empty() || queue.len() < chunk_size {
-            return None; // 如果队列为空，返回 None
+    /// 修改音频队列积压时的处理方式，只影响读取端，不需要重新打开设备
+    pub fn set_overflow_policy(&mut self, overflow_policy: OverflowPolicy) {
+        self.overflow_policy = overflow_policy;
+        self.lagging = false;
+    }
+
+    /// 每个声道累计丢弃的采样数
+    pub fn dropped_samples(&self) -> usize {
+        self.samples_queue.dropped() + self.dropped_oldest
+    }
+}
+
+impl AudioSource for Recorder {
+    /// 多声道输出时返回所有声道的平均值
+    fn pop_head_sample(&mut self, chunk_size: usize) -> Option<Vec<f32>> {
+        self.pop_head_channels(chunk_size)
+            .map(|channel_samples| downmix(&channel_samples))
+    }
+
+    fn channels(&self) -> usize {
+        self.samples_queue.channels()
+    }
+
+    fn pop_head_channels(&mut self, chunk_size: usize) -> Option<Vec<Vec<f32>>> {
+        let queued = self.samples_queue.len();
+        match self.overflow_policy {
+            OverflowPolicy::DropOldest => {
+                // 音频回调无法移动读取位置，由读取端丢弃最早的数据
+                let limit = self
+                    .samples_queue
+                    .capacity()
+                    .saturating_sub(DROP_OLDEST_HEADROOM);
+                if queued > limit {
+                    self.dropped_oldest += self.samples_queue.skip(queued - limit);
+                }
+            }
+            OverflowPolicy::DropNewest => {}
+            OverflowPolicy::SkipOnline => {
+                if queued > LAGGING_START {
+                    self.lagging = true;
+                } else if queued < LAGGING_END {
+                    self.lagging = false;
+                }
+            }
         }
+        // 所有声道同步写入，数据不足时返回 None
+        self.samples_queue.pop(chunk_size)
+    }
 
-        // 确保不会超出队列长度
-        let chunk_size = chunk_size.min(queue.len());
-        let head_samples = queue.drain(0..chunk_size).collect::<Vec<f32>>();
+    fn attach(&mut self) {
+        // 录音在识别开始前就已经运行，之前积压和丢弃的音频与本次识别无关
+        let queued = self.samples_queue.len();
+        self.samples_queue.skip(queued);
+        self.reported_dropped = self.dropped_samples();
+        self.lagging = false;
+    }
+
+    fn take_dropped(&mut self) -> usize {
+        let dropped = self.dropped_samples();
+        let new_dropped = dropped - self.reported_dropped;
+        self.reported_dropped = dropped;
+        new_dropped
+    }
 
-        if head_samples.is_empty() {
-            None // 如果没有样本被弹出，返回 None
-        } else {
-            Some(head_samples) // 返回弹出的样本
+    fn is_lagging(&self) -> bool {
+        self.lagging
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        if self.error_reported {
+            return None;
         }
+        let error = self.stream_error.lock().expect("获取锁失败").clone();
+        self.error_reported = error.is_some();
+        error
+    }
+
+    fn notifier(&self) -> Option<ReadNotifier> {
+        Some(self.samples_queue.notifier())
     }
 }
 
 fn process_samples<T: NumCast + Bounded + FromPrimitive + Copy + 'static>(
     data: &[T],
-    source_rate: u32,
-    target_rate: u32,
-    samples_queue: &Arc<Mutex<Vec<f32>>>,
+    channels: usize,
+    channel_mode: ChannelMode,
+    resamplers: &mut [Resampler],
+    producer: &mut Producer,
 ) {
     // 早期返回空数据
     if data.is_empty() {
@@ -148,30 +318,35 @@ fn process_samples<T: NumCast + Bounded + FromPrimitive + Copy + 'static>(
     // 标准化
     let normal_data = normalization(data);
 
-    // 重采样至目标采样率
-    let resample_data = resample(normal_data, source_rate, target_rate);
+    // 按声道拆分并重采样至目标采样率
+    let resample_data: Vec<Vec<f32>> = split_channels(&normal_data, channels, channel_mode)
+        .iter()
+        .zip(resamplers.iter_mut())
+        .map(|(channel_data, resampler)| resampler.process(channel_data))
+        .collect();
 
     // 如果重采样后也是空的，直接返回
-    if resample_data.is_empty() {
+    if resample_data.iter().all(|channel_data| channel_data.is_empty()) {
         return;
     }
 
-    // 尽量减少锁持有时间
-    {
-        let mut queue = samples_queue.lock().expect("获取锁失败");
-
-        // 简单的内存保护：如果队列太大，移除旧的样本
-        let new_total = queue.len() + resample_data.len();
-
-        if new_total > MAX_QUEUE_SIZE {
-            let excess = new_total - MAX_QUEUE_SIZE;
-            queue.drain(0..excess);
-        }
+    // 写入环形缓冲区不需要加锁，队列已满时丢弃新数据并计数
+    producer.push(&resample_data);
+}
 
-        // 预留空间避免多次重新分配
-        queue.reserve(resample_data.len());
-        queue.extend(resample_data);
-    } // 锁在这里释放
+/// 24 位整数的有效范围只有 i32 的低 24 位，不能按 i32 的范围标准化，先转换为 f32
+fn process_i24_samples(
+    data: &[I24],
+    channels: usize,
+    channel_mode: ChannelMode,
+    resamplers: &mut [Resampler],
+    producer: &mut Producer,
+) {
+    let data: Vec<f32> = data
+        .iter()
+        .map(|sample| sample.to_sample::<f32>())
+        .collect();
+    process_samples(&data, channels, channel_mode, resamplers, producer);
 }
 
 fn is_float<T: 'static>() -> bool {
@@ -217,53 +392,26 @@ fn normalization<T: NumCast + Bounded + Copy + 'static>(data: &[T]) -> Vec<f32>
             .collect::<Vec<f32>>()
     }
 }
-/// 采用线性插值的方式进行重采样
-pub fn resample(data: Vec<f32>, source_sample_rate: u32, target_sample_rate: u32) -> Vec<f32> {
-    // 如果源采样率等于目标采样率或者数据为空，则直接返回原始数据
-    if source_sample_rate == target_sample_rate || data.is_empty() {
-        return data; // 采样率相同，无需重采样
-    }
 
-    let source_len = data.len();
-    let ratio = source_sample_rate as f64 / target_sample_rate as f64;
-    let target_len = ((source_len as f64) / ratio).ceil() as usize;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // 预分配内存，避免动态扩容
-    let mut resampled = Vec::with_capacity(target_len);
-
-    // 如果是整数倍下采样，使用更简单的算法
-    if ratio.fract() == 0.0 && ratio >= 1.0 {
-        let step = ratio as usize;
-        data.iter().step_by(step).for_each(|&x| resampled.push(x));
-        return resampled;
-    }
-
-    for i in 0..target_len {
-        // 计算目标点在原始数据中的对应位置（浮点数）
-        let source_index_float =
-            (i as f64) * (source_sample_rate as f64) / (target_sample_rate as f64);
-
-        // 获取左右两个最近的原始数据索引
-        let left_index = source_index_float.floor() as usize;
-        let right_index = source_index_float.ceil() as usize;
-
-        // 如果左索引超出了原始数据范围，直接使用最后一个点的值 (这种情况通常发生在最后一个点或接近最后一个点时)
-        if left_index >= source_len - 1 {
-            resampled[i] = data[source_len - 1];
-            continue;
-        }
-
-        // 获取左右两个点的值
-        let left_value = data[left_index];
-        let right_value = data[right_index];
-
-        // 计算插值系数
-        let alpha = (source_index_float - (left_index as f64)) as f32;
-
-        // 线性插值
-        let interpolated_value = left_value * (1.0 - alpha) + right_value * alpha;
-        resampled[i] = interpolated_value;
+    #[test]
+    fn attach_discards_earlier_drops() {
+        let (mut producer, samples_queue) = ring_buffer(1, 100);
+        let mut recorder = Recorder {
+            samples_queue,
+            ..Recorder::unavailable()
+        };
+        // 识别开始前队列已满并丢弃了部分音频
+        producer.push(&[vec![0.0; 150]]);
+        recorder.attach();
+        assert_eq!(recorder.take_dropped(), 0);
+        assert!(recorder.pop_head_channels(1).is_none());
+
+        producer.push(&[vec![0.0; 120]]);
+        assert_eq!(recorder.take_dropped(), 20);
+        assert_eq!(recorder.take_dropped(), 0);
     }
-
-    resampled
 }
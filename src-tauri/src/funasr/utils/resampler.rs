@@ -0,0 +1,292 @@
+use anyhow::{bail, Result};
+use std::f64::consts::PI;
+
+/// 滤波器单侧过零点个数，越大过渡带越窄
+const ZERO_CROSSINGS: f64 = 16.0;
+/// 截止频率相对于奈奎斯特频率的比例，留出过渡带避免混叠
+const ROLLOFF: f64 = 0.92;
+/// Kaiser 窗参数
+const KAISER_BETA: f64 = 8.0;
+
+/// 带限多相重采样器
+///
+/// 采样率转换比例化简为 L/M（先插值 L 倍再抽取 M 倍），
+/// 使用 Kaiser 窗加权的 sinc 低通滤波器，按相位预先拆分为 L 组系数，
+/// 每个输出采样只需计算一组系数与输入的点积。
+///
+/// 滤波器所需的历史数据在多次调用之间保留，分块处理的结果与一次性处理完全一致，
+/// 音频回调的块边界不会产生咔哒声。
+pub struct Resampler {
+    /// 插值倍数 L
+    up: usize,
+    /// 抽取倍数 M
+    down: usize,
+    /// 每个相位的滤波器系数，共 L 组
+    filters: Vec<Vec<f32>>,
+    /// 每组滤波器系数的长度
+    taps: usize,
+    /// 待处理的输入数据，开头保留滤波器所需的历史数据
+    buffer: Vec<f32>,
+    /// 下一个输出采样对应的第一个输入采样在 `buffer` 中的位置
+    position: usize,
+    /// 下一个输出采样的相位
+    phase: usize,
+    /// 已输入的采样数
+    input_count: usize,
+    /// 已输出的采样数
+    output_count: usize,
+}
+
+impl Resampler {
+    /// 创建重采样器
+    /// # 参数
+    /// - `source_sample_rate`: 输入采样率
+    /// - `target_sample_rate`: 输出采样率
+    /// # 错误
+    /// - 采样率为 0
+    pub fn new(source_sample_rate: u32, target_sample_rate: u32) -> Result<Self> {
+        if source_sample_rate == 0 || target_sample_rate == 0 {
+            bail!(
+                "无效的采样率: {}Hz -> {}Hz",
+                source_sample_rate,
+                target_sample_rate
+            );
+        }
+        let divisor = gcd(source_sample_rate as usize, target_sample_rate as usize);
+        let up = target_sample_rate as usize / divisor;
+        let down = source_sample_rate as usize / divisor;
+        let (filters, taps) = if up == down {
+            (Vec::new(), 0)
+        } else {
+            design_filters(up, down)
+        };
+        let mut resampler = Self {
+            up,
+            down,
+            filters,
+            taps,
+            buffer: Vec::new(),
+            position: 0,
+            phase: 0,
+            input_count: 0,
+            output_count: 0,
+        };
+        resampler.reset();
+        Ok(resampler)
+    }
+
+    /// 输入输出采样率是否相同
+    pub fn is_passthrough(&self) -> bool {
+        self.up == self.down
+    }
+
+    /// 清空滤波器状态
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        // 第一个输出采样之前的输入视为静音
+        self.buffer.resize((self.taps / 2).saturating_sub(1), 0.0);
+        self.position = 0;
+        self.phase = 0;
+        self.input_count = 0;
+        self.output_count = 0;
+    }
+
+    /// 处理一块输入数据，返回当前可以计算的全部输出
+    /// 由于滤波器需要未来的输入，输出相对输入有约 `taps / 2` 个输入采样的延迟
+    pub fn process(&mut self, data: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() {
+            return data.to_vec();
+        }
+        self.input_count += data.len();
+        self.buffer.extend_from_slice(data);
+        let mut output = Vec::with_capacity(data.len() * self.up / self.down + 1);
+        while self.position + self.taps <= self.buffer.len() {
+            let window = &self.buffer[self.position..self.position + self.taps];
+            let sample = self.filters[self.phase]
+                .iter()
+                .zip(window)
+                .map(|(&h, &x)| h * x)
+                .sum::<f32>();
+            output.push(sample);
+            self.phase += self.down;
+            self.position += self.phase / self.up;
+            self.phase %= self.up;
+        }
+        // 移除不再需要的输入
+        self.buffer.drain(..self.position.min(self.buffer.len()));
+        self.position = 0;
+        self.output_count += output.len();
+        output
+    }
+
+    /// 输入结束时调用，输出滤波器中剩余的数据并重置状态
+    pub fn flush(&mut self) -> Vec<f32> {
+        if self.is_passthrough() {
+            return Vec::new();
+        }
+        // 期望的总输出长度
+        let expected = (self.input_count * self.up).div_ceil(self.down);
+        let remaining = expected.saturating_sub(self.output_count);
+        // 补充静音，使最后的输入采样也能被完整计算
+        let mut output = self.process(&vec![0.0; self.taps]);
+        output.truncate(remaining);
+        self.reset();
+        output
+    }
+}
+
+/// 一次性重采样全部数据
+pub fn resample(
+    data: Vec<f32>,
+    source_sample_rate: u32,
+    target_sample_rate: u32,
+) -> Result<Vec<f32>> {
+    // 如果源采样率等于目标采样率或者数据为空，则直接返回原始数据
+    if source_sample_rate == target_sample_rate || data.is_empty() {
+        return Ok(data);
+    }
+    let mut resampler = Resampler::new(source_sample_rate, target_sample_rate)?;
+    let mut resampled = resampler.process(&data);
+    resampled.extend(resampler.flush());
+    Ok(resampled)
+}
+
+/// 设计多相滤波器组
+/// # 返回值
+/// - 每个相位的滤波器系数，以及每组系数的长度
+fn design_filters(up: usize, down: usize) -> (Vec<Vec<f32>>, usize) {
+    // 截止频率，相对于输入信号的奈奎斯特频率
+    let cutoff = (up as f64 / down as f64).min(1.0) * ROLLOFF;
+    // 滤波器单侧宽度，单位：输入采样
+    let half_width = ZERO_CROSSINGS / cutoff;
+    let half = half_width.ceil() as usize;
+    let taps = 2 * half;
+    let filters = (0..up)
+        .map(|phase| {
+            let fraction = phase as f64 / up as f64;
+            let mut filter: Vec<f64> = (0..taps)
+                .map(|i| {
+                    // 输出采样与第 i 个输入采样之间的距离
+                    let x = fraction + (half - 1) as f64 - i as f64;
+                    if x.abs() >= half_width {
+                        return 0.0;
+                    }
+                    cutoff * sinc(cutoff * x) * kaiser(x / half_width)
+                })
+                .collect();
+            // 归一化，保证直流增益为 1
+            let sum: f64 = filter.iter().sum();
+            filter.iter_mut().for_each(|h| *h /= sum);
+            filter.into_iter().map(|h| h as f32).collect()
+        })
+        .collect();
+    (filters, taps)
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Kaiser 窗，`r` 取值范围 [-1,1]
+fn kaiser(r: f64) -> f64 {
+    bessel_i0(KAISER_BETA * (1.0 - r * r).max(0.0).sqrt()) / bessel_i0(KAISER_BETA)
+}
+
+/// 第一类零阶修正贝塞尔函数
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = x / 2.0;
+    for k in 1..50 {
+        term *= half_x / k as f64;
+        let term_sqr = term * term;
+        sum += term_sqr;
+        if term_sqr < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
+
+pub(super) fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f64, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (0.5 * (2.0 * PI * frequency * i as f64 / sample_rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    /// 单个频率分量的幅度
+    fn amplitude(data: &[f32], frequency: f64, sample_rate: u32) -> f64 {
+        let (re, im) = data
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (i, &x)| {
+                let phase = 2.0 * PI * frequency * i as f64 / sample_rate as f64;
+                (re + x as f64 * phase.cos(), im - x as f64 * phase.sin())
+            });
+        2.0 * (re * re + im * im).sqrt() / data.len() as f64
+    }
+
+    #[test]
+    fn chunked_matches_one_shot() {
+        let input = sine(440.0, 48000, 48000);
+        let expected = resample(input.clone(), 48000, 16000).unwrap();
+        let mut resampler = Resampler::new(48000, 16000).unwrap();
+        let mut output = Vec::new();
+        for chunk in input.chunks(479) {
+            output.extend(resampler.process(chunk));
+        }
+        output.extend(resampler.flush());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn output_length_matches_ratio() {
+        for (source, len, expected) in [
+            (44100, 44100, 16000),
+            (48000, 48000, 16000),
+            (44100, 1000, 363),
+        ] {
+            let output = resample(vec![0.1; len], source, 16000).unwrap();
+            assert_eq!(output.len(), expected, "{}Hz -> 16000Hz", source);
+        }
+    }
+
+    #[test]
+    fn sine_keeps_frequency_and_amplitude() {
+        for source in [44100, 48000] {
+            let output = resample(sine(1000.0, source, source as usize), source, 16000).unwrap();
+            // 跳过首尾滤波器的过渡部分
+            let middle = &output[1000..15000];
+            let peak = amplitude(middle, 1000.0, 16000);
+            assert!((peak - 0.5).abs() < 0.01, "{}Hz: 幅度 {}", source, peak);
+            // 能量集中在 1kHz，邻近频率几乎没有分量
+            assert!(amplitude(middle, 1100.0, 16000) < 0.01);
+            let crossings = middle
+                .windows(2)
+                .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+                .count();
+            assert!(
+                (874..=876).contains(&crossings),
+                "{}Hz: 过零次数 {}",
+                source,
+                crossings
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use atomic_waker::AtomicWaker;
+use std::future::poll_fn;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+/// 单生产者单消费者的多声道环形缓冲区
+///
+/// 所有声道共用读写位置，生产者每次写入时各声道长度相同。
+/// 采样以 `f32` 的位模式保存在原子变量中，读写都不需要加锁，
+/// 音频回调线程不会因为消费者持有锁而阻塞。
+/// 生产者写入后通过 `AtomicWaker` 唤醒等待数据的消费者，同样不需要加锁。
+struct RingBuffer {
+    /// 每个声道的数据
+    lanes: Vec<Box<[AtomicU32]>>,
+    /// 每个声道最多保存的采样数
+    capacity: usize,
+    /// 已读取的采样总数，只由消费者修改
+    read: AtomicUsize,
+    /// 已写入的采样总数，只由生产者修改
+    write: AtomicUsize,
+    /// 缓冲区已满时丢弃的采样总数，只由生产者修改
+    dropped: AtomicUsize,
+    /// 等待数据的消费者任务
+    waker: AtomicWaker,
+    /// 生产者是否已经释放（如录音流被关闭），之后不会再有数据写入
+    closed: AtomicBool,
+}
+
+impl RingBuffer {
+    fn len(&self) -> usize {
+        let write = self.write.load(Ordering::Acquire);
+        let read = self.read.load(Ordering::Acquire);
+        write.wrapping_sub(read)
+    }
+}
+
+/// 创建环形缓冲区，返回生产者和消费者
+/// # 参数
+/// - `channels`: 声道数
+/// - `capacity`: 每个声道最多保存的采样数
+pub fn ring_buffer(channels: usize, capacity: usize) -> (Producer, Consumer) {
+    let lanes = (0..channels.max(1))
+        .map(|_| (0..capacity).map(|_| AtomicU32::new(0)).collect())
+        .collect();
+    let buffer = Arc::new(RingBuffer {
+        lanes,
+        capacity,
+        read: AtomicUsize::new(0),
+        write: AtomicUsize::new(0),
+        dropped: AtomicUsize::new(0),
+        waker: AtomicWaker::new(),
+        closed: AtomicBool::new(false),
+    });
+    (
+        Producer {
+            buffer: Arc::clone(&buffer),
+        },
+        Consumer { buffer },
+    )
+}
+
+/// 环形缓冲区的写入端，只能在一个线程中使用
+pub struct Producer {
+    buffer: Arc<RingBuffer>,
+}
+
+impl Producer {
+    /// 写入每个声道的数据
+    /// 各声道长度不一致时以最短的为准，缓冲区已满时丢弃放不下的新数据
+    /// # 返回值
+    /// - 每个声道实际写入的采样数
+    pub fn push(&mut self, channel_data: &[Vec<f32>]) -> usize {
+        let buffer = &self.buffer;
+        let length = channel_data.iter().map(|c| c.len()).min().unwrap_or(0);
+        let write = buffer.write.load(Ordering::Relaxed);
+        let read = buffer.read.load(Ordering::Acquire);
+        let free = buffer.capacity - write.wrapping_sub(read);
+        if length > free {
+            buffer.dropped.fetch_add(length - free, Ordering::Relaxed);
+        }
+        let length = length.min(free);
+        if length == 0 {
+            return 0;
+        }
+        for (lane, data) in buffer.lanes.iter().zip(channel_data) {
+            for (i, &sample) in data[..length].iter().enumerate() {
+                lane[write.wrapping_add(i) % buffer.capacity]
+                    .store(sample.to_bits(), Ordering::Relaxed);
+            }
+        }
+        // 数据写入完成后再更新写入位置，保证消费者读到完整的数据
+        buffer
+            .write
+            .store(write.wrapping_add(length), Ordering::Release);
+        buffer.waker.wake();
+        length
+    }
+}
+
+impl Drop for Producer {
+    fn drop(&mut self) {
+        // 唤醒等待中的消费者，避免一直等待不会再写入的数据
+        self.buffer.closed.store(true, Ordering::Release);
+        self.buffer.waker.wake();
+    }
+}
+
+/// 环形缓冲区的读取端，只能在一个线程中使用
+pub struct Consumer {
+    buffer: Arc<RingBuffer>,
+}
+
+impl Consumer {
+    /// 声道数
+    pub fn channels(&self) -> usize {
+        self.buffer.lanes.len()
+    }
+
+    /// 每个声道可读取的采样数
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// 每个声道最多保存的采样数
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity
+    }
+
+    /// 缓冲区已满时生产者丢弃的采样总数
+    pub fn dropped(&self) -> usize {
+        self.buffer.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 丢弃每个声道头部最多 `count` 个采样
+    /// # 返回值
+    /// - 每个声道实际丢弃的采样数
+    pub fn skip(&mut self, count: usize) -> usize {
+        let buffer = &self.buffer;
+        let read = buffer.read.load(Ordering::Relaxed);
+        let write = buffer.write.load(Ordering::Acquire);
+        let count = count.min(write.wrapping_sub(read));
+        buffer
+            .read
+            .store(read.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    /// 从每个声道头部取出 `chunk_size` 个采样，数据不足时返回 None
+    pub fn pop(&mut self, chunk_size: usize) -> Option<Vec<Vec<f32>>> {
+        let buffer = &self.buffer;
+        let read = buffer.read.load(Ordering::Relaxed);
+        let write = buffer.write.load(Ordering::Acquire);
+        if chunk_size == 0 || write.wrapping_sub(read) < chunk_size {
+            return None;
+        }
+        let head_samples = buffer
+            .lanes
+            .iter()
+            .map(|lane| {
+                (0..chunk_size)
+                    .map(|i| {
+                        f32::from_bits(
+                            lane[read.wrapping_add(i) % buffer.capacity].load(Ordering::Relaxed),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        // 数据读取完成后再更新读取位置，生产者才能覆盖这部分空间
+        buffer
+            .read
+            .store(read.wrapping_add(chunk_size), Ordering::Release);
+        Some(head_samples)
+    }
+
+    /// 等待数据写入，可在不持有消费者的情况下等待
+    pub fn notifier(&self) -> ReadNotifier {
+        ReadNotifier {
+            buffer: Arc::clone(&self.buffer),
+        }
+    }
+}
+
+/// 等待环形缓冲区中的数据
+#[derive(Clone)]
+pub struct ReadNotifier {
+    buffer: Arc<RingBuffer>,
+}
+
+impl ReadNotifier {
+    /// 等待每个声道至少有 `chunk_size` 个采样可读取
+    /// 只有生产者写入数据或被释放时才会唤醒，同一时间只能有一个任务等待
+    /// # 返回值
+    /// - 数据足够时返回 true，生产者已释放且数据不足时返回 false
+    pub async fn readable(&self, chunk_size: usize) -> bool {
+        poll_fn(|cx| {
+            if let Some(readable) = self.check(chunk_size) {
+                return Poll::Ready(readable);
+            }
+            // 注册后再检查一次，避免错过注册前的写入
+            self.buffer.waker.register(cx.waker());
+            match self.check(chunk_size) {
+                Some(readable) => Poll::Ready(readable),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// 数据足够时返回 true，生产者已释放时返回 false，否则需要继续等待
+    fn check(&self, chunk_size: usize) -> Option<bool> {
+        // 先读取释放标志，保证生产者释放前写入的数据都能被看到
+        let closed = self.buffer.closed.load(Ordering::Acquire);
+        if self.buffer.len() >= chunk_size {
+            Some(true)
+        } else if closed {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn readable_wakes_on_push() {
+        let (mut producer, mut consumer) = ring_buffer(2, 100);
+        let notifier = consumer.notifier();
+        let handle = thread::spawn(move || {
+            for _ in 0..4 {
+                thread::sleep(Duration::from_millis(5));
+                producer.push(&[vec![0.5; 10], vec![-0.5; 10]]);
+            }
+            producer
+        });
+        assert!(notifier.readable(40).await);
+        let data = consumer.pop(40).unwrap();
+        assert_eq!(data, [vec![0.5; 40], vec![-0.5; 40]]);
+        drop(handle.join().unwrap());
+        // 生产者释放后不再等待
+        assert!(!notifier.readable(1).await);
+    }
+
+    #[test]
+    fn push_drops_overflow() {
+        let (mut producer, mut consumer) = ring_buffer(1, 100);
+        assert_eq!(producer.push(&[vec![1.0; 80]]), 80);
+        assert_eq!(producer.push(&[vec![2.0; 40]]), 20);
+        assert_eq!(consumer.dropped(), 20);
+        assert_eq!(consumer.skip(70), 70);
+        assert_eq!(
+            consumer.pop(30),
+            Some(vec![[vec![1.0; 10], vec![2.0; 20]].concat()])
+        );
+    }
+}
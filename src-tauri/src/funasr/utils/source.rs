@@ -0,0 +1,383 @@
+use crate::funasr::utils::constant::SAMPLE_RATE;
+use crate::funasr::utils::read_audio_file;
+use crate::funasr::utils::{ReadNotifier, Resampler};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io::Read;
+use std::mem::take;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 音频源
+/// 识别流程通过该接口获取 16kHz 单声道 [-1,1] 范围的音频数据
+pub trait AudioSource: Send {
+    /// 从音频源头部取出 `chunk_size` 个采样
+    /// 数据不足时返回 None，有限音频源在结束前会返回最后不足 `chunk_size` 的数据
+    fn pop_head_sample(&mut self, chunk_size: usize) -> Option<Vec<f32>>;
+
+    /// 音频源是否已经结束，实时音频源永远不会结束
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    /// 输出的声道数
+    fn channels(&self) -> usize {
+        1
+    }
+
+    /// 从每个声道头部取出 `chunk_size` 个采样，规则与 `pop_head_sample` 一致
+    fn pop_head_channels(&mut self, chunk_size: usize) -> Option<Vec<Vec<f32>>> {
+        self.pop_head_sample(chunk_size)
+            .map(|head_samples| vec![head_samples])
+    }
+
+    /// 开始识别时调用，丢弃识别开始前积压的音频和丢弃计数
+    fn attach(&mut self) {}
+
+    /// 自上次调用以来每个声道丢弃的采样数，实时音频源在识别跟不上时会丢弃音频
+    fn take_dropped(&mut self) -> usize {
+        0
+    }
+
+    /// 音频是否积压过多，需要跳过实时识别以追上进度
+    fn is_lagging(&self) -> bool {
+        false
+    }
+
+    /// 音频源出错时返回错误信息，每个错误只返回一次
+    fn take_error(&mut self) -> Option<String> {
+        None
+    }
+
+    /// 用于等待新数据的通知，返回 None 时调用方需要自行轮询
+    fn notifier(&self) -> Option<ReadNotifier> {
+        None
+    }
+}
+
+/// 音频文件音频源
+/// 文件在创建时一次性解码，读取速度不受实时限制
+pub struct FileSource {
+    samples: Vec<f32>,
+    position: usize,
+}
+
+impl FileSource {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::from_samples(read_audio_file(path)?))
+    }
+
+    /// 使用 16kHz 单声道音频数据创建音频源
+    pub fn from_samples(samples: Vec<f32>) -> Self {
+        Self {
+            samples,
+            position: 0,
+        }
+    }
+}
+
+impl AudioSource for FileSource {
+    fn pop_head_sample(&mut self, chunk_size: usize) -> Option<Vec<f32>> {
+        if self.position >= self.samples.len() {
+            return None;
+        }
+        let end = (self.position + chunk_size).min(self.samples.len());
+        let head_samples = self.samples[self.position..end].to_vec();
+        self.position = end;
+        Some(head_samples)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.position >= self.samples.len()
+    }
+}
+
+/// 原始 PCM 采样格式
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum PcmFormat {
+    /// 16位有符号整数，小端序
+    I16,
+    /// 32位浮点数，小端序
+    F32,
+}
+
+impl PcmFormat {
+    /// 每个采样的字节数
+    fn sample_size(&self) -> usize {
+        match self {
+            PcmFormat::I16 => 2,
+            PcmFormat::F32 => 4,
+        }
+    }
+
+    /// 将字节转换为 [-1,1] 范围的采样
+    fn decode(&self, bytes: &[u8]) -> f32 {
+        match self {
+            PcmFormat::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            PcmFormat::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// 标准输入的原始字节
+/// 标准输入只能由一个线程读取，读取线程在第一次使用时启动，之后的音频源共用该线程
+struct StdinReader {
+    bytes: Mutex<Vec<u8>>,
+    finished: AtomicBool,
+}
+
+static STDIN_READER: LazyLock<Arc<StdinReader>> = LazyLock::new(|| {
+    let reader = Arc::new(StdinReader {
+        bytes: Mutex::new(Vec::new()),
+        finished: AtomicBool::new(false),
+    });
+    let thread_reader = Arc::clone(&reader);
+    thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
+        let mut buffer = vec![0u8; 6400];
+        loop {
+            let read = match stdin.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            thread_reader
+                .bytes
+                .lock()
+                .expect("获取锁失败")
+                .extend_from_slice(&buffer[..read]);
+        }
+        thread_reader.finished.store(true, Ordering::Release);
+    });
+    reader
+});
+
+/// 标准输入音频源
+/// 从标准输入读取单声道原始 PCM 数据，直到标准输入关闭
+pub struct StdinSource {
+    reader: Arc<StdinReader>,
+    format: PcmFormat,
+    resampler: Resampler,
+    /// 不足一个采样的字节
+    pending: Vec<u8>,
+    /// 已解码并重采样的音频
+    samples: Vec<f32>,
+    /// 标准输入关闭后重采样器中剩余的音频已取出
+    flushed: bool,
+}
+
+impl StdinSource {
+    /// # 参数
+    /// - `format`: 采样格式
+    /// - `sample_rate`: 输入数据的采样率，将被重采样至 `SAMPLE_RATE`
+    pub fn new(format: PcmFormat, sample_rate: u32) -> Result<Self> {
+        Ok(Self {
+            reader: Arc::clone(&STDIN_READER),
+            format,
+            resampler: Resampler::new(sample_rate, SAMPLE_RATE as u32)?,
+            pending: Vec::new(),
+            samples: Vec::new(),
+            flushed: false,
+        })
+    }
+
+    /// 解码读取线程已读取的字节
+    fn decode_available(&mut self) {
+        // 先读取结束标志，保证之后取出的字节包含全部数据
+        let finished = self.reader.finished.load(Ordering::Acquire);
+        let bytes = take(&mut *self.reader.bytes.lock().expect("获取锁失败"));
+        self.pending.extend_from_slice(&bytes);
+        let sample_size = self.format.sample_size();
+        let complete = self.pending.len() / sample_size * sample_size;
+        let samples: Vec<f32> = self.pending[..complete]
+            .chunks_exact(sample_size)
+            .map(|bytes| self.format.decode(bytes))
+            .collect();
+        self.pending.drain(..complete);
+        self.samples.extend(self.resampler.process(&samples));
+        if finished && !self.flushed {
+            self.samples.extend(self.resampler.flush());
+            self.flushed = true;
+        }
+    }
+}
+
+impl AudioSource for StdinSource {
+    fn pop_head_sample(&mut self, chunk_size: usize) -> Option<Vec<f32>> {
+        self.decode_available();
+        // 标准输入关闭后返回剩余的全部数据
+        if self.samples.is_empty() || (self.samples.len() < chunk_size && !self.flushed) {
+            return None;
+        }
+        let chunk_size = chunk_size.min(self.samples.len());
+        Some(self.samples.drain(0..chunk_size).collect())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.flushed && self.samples.is_empty()
+    }
+}
+
+/// 合成信号类型
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Signal {
+    /// 正弦波测试音
+    Tone { frequency: f32, amplitude: f32 },
+    /// 静音
+    Silence,
+}
+
+/// 合成信号音频源
+/// 用于在没有输入设备时测试识别流程
+/// 有限时长的信号一次性生成，无限生成的信号按实时速度生成
+pub struct SignalSource {
+    signal: Signal,
+    /// 已生成的采样数
+    position: usize,
+    /// 总采样数，None 表示无限生成
+    total: Option<usize>,
+    /// 创建时间，无限生成时用于按实时速度生成
+    started: Instant,
+}
+
+impl SignalSource {
+    /// # 参数
+    /// - `signal`: 信号类型
+    /// - `duration`: 信号时长，None 表示无限生成
+    pub fn new(signal: Signal, duration: Option<Duration>) -> Self {
+        Self {
+            signal,
+            position: 0,
+            total: duration.map(|d| (d.as_secs_f64() * SAMPLE_RATE as f64) as usize),
+            started: Instant::now(),
+        }
+    }
+
+    /// 生成下一块信号
+    /// # 参数
+    /// - `chunk_size`: 块大小
+    /// - `elapsed`: 创建以来经过的时长，无限生成时不超过该时长
+    fn generate(&mut self, chunk_size: usize, elapsed: Duration) -> Option<Vec<f32>> {
+        let end = match self.total {
+            Some(total) => (self.position + chunk_size).min(total),
+            None => {
+                // 不超过创建以来实际经过的时长，避免识别流程空转
+                let available = (elapsed.as_secs_f64() * SAMPLE_RATE as f64) as usize;
+                let end = self.position + chunk_size;
+                if end > available {
+                    return None;
+                }
+                end
+            }
+        };
+        if end <= self.position {
+            return None;
+        }
+        let head_samples = match self.signal {
+            Signal::Tone {
+                frequency,
+                amplitude,
+            } => (self.position..end)
+                .map(|i| {
+                    let phase = 2.0 * PI * frequency as f64 * i as f64 / SAMPLE_RATE as f64;
+                    amplitude * phase.sin() as f32
+                })
+                .collect(),
+            Signal::Silence => vec![0.0; end - self.position],
+        };
+        self.position = end;
+        Some(head_samples)
+    }
+}
+
+impl AudioSource for SignalSource {
+    fn pop_head_sample(&mut self, chunk_size: usize) -> Option<Vec<f32>> {
+        self.generate(chunk_size, self.started.elapsed())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.total.is_some_and(|total| self.position >= total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 依次读取音频源的全部数据
+    fn drain(source: &mut dyn AudioSource, chunk_size: usize) -> Vec<Vec<f32>> {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = source.pop_head_sample(chunk_size) {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    #[test]
+    fn file_source_returns_last_partial_chunk() {
+        let mut source = FileSource::from_samples(vec![0.5; 4000]);
+        let chunks = drain(&mut source, 1600);
+        let lengths: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [1600, 1600, 800]);
+        assert!(source.is_finished());
+    }
+
+    #[test]
+    fn finite_signal_source_has_exact_duration() {
+        let signal = Signal::Tone {
+            frequency: 440.0,
+            amplitude: 0.5,
+        };
+        let mut source = SignalSource::new(signal, Some(Duration::from_millis(250)));
+        let samples: Vec<f32> = drain(&mut source, 1600).concat();
+        assert_eq!(samples.len(), SAMPLE_RATE / 4);
+        assert!(samples.iter().all(|x| x.abs() <= 0.5));
+        assert!(source.is_finished());
+    }
+
+    #[test]
+    fn endless_signal_source_is_paced() {
+        let mut source = SignalSource::new(Signal::Silence, None);
+        // 经过的时长还不足一个块时不生成
+        assert!(source.generate(1600, Duration::from_millis(99)).is_none());
+        let chunk = source.generate(1600, Duration::from_millis(150));
+        assert_eq!(chunk.map(|chunk| chunk.len()), Some(1600));
+        assert!(source.generate(1600, Duration::from_millis(150)).is_none());
+        let chunk = source.generate(1600, Duration::from_millis(200));
+        assert_eq!(chunk.map(|chunk| chunk.len()), Some(1600));
+        assert!(!source.is_finished());
+    }
+
+    /// 使用合成的信号拼接测试音频，不需要准备音频文件
+    #[test]
+    fn fixture_built_from_signals() {
+        let tone = Signal::Tone {
+            frequency: 300.0,
+            amplitude: 0.5,
+        };
+        let mut samples = vec![0.0; SAMPLE_RATE / 2];
+        samples.extend(
+            drain(
+                &mut SignalSource::new(tone, Some(Duration::from_secs(1))),
+                1600,
+            )
+            .concat(),
+        );
+        samples.extend(vec![0.0; SAMPLE_RATE / 2]);
+        let mut source = FileSource::from_samples(samples.clone());
+        let chunks = drain(&mut source, 1600);
+        assert_eq!(chunks.concat(), samples);
+        // 前后各 0.5s 静音，中间 1s 为信号
+        let voiced: Vec<bool> = chunks
+            .iter()
+            .map(|chunk| chunk.iter().any(|x| x.abs() > 0.1))
+            .collect();
+        assert_eq!(
+            voiced,
+            [vec![false; 5], vec![true; 10], vec![false; 5]].concat()
+        );
+    }
+}
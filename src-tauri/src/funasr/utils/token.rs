@@ -42,3 +42,198 @@ pub fn read_token(path: impl AsRef<Path>) -> Result<TokenIdConverter> {
 
     Ok(TokenIdConverter::new(token_list))
 }
+
+/// SentencePiece 的词首标记
+const WORD_BOUNDARY: char = '▁';
+/// BPE 的词内连接标记
+const CONTINUATION: &str = "@@";
+
+/// 将 token 拼接为文本
+/// - `▁` 开头的 token 为新单词，`@@` 结尾的 token 与下一个 token 直接连接
+/// - 中文、日文之间不加空格，中文与英文单词之间加空格，标点前不加空格
+/// - 去除 `<s>`、`<unk>`、`<|zh|>` 等特殊 token
+///
+/// 流式识别每次只解码一部分 token，同一路音频流需要使用同一个实例，
+/// 以便在两次解码结果之间正确地添加空格
+#[derive(Default)]
+pub struct Detokenizer {
+    /// 已输出文本的最后一个字符
+    last: Option<char>,
+    /// 上一个 token 以 `@@` 结尾，下一个 token 直接连接
+    glue: bool,
+    /// 上一个 token 为单独的 `▁`，下一个 token 为新单词
+    word_start: bool,
+    /// token 是否来自 SentencePiece，此时不以 `▁` 开头的 token 与上一个 token 属于同一单词
+    sentencepiece: bool,
+}
+
+impl Detokenizer {
+    /// 拼接一次解码得到的 token，返回新增的文本
+    pub fn push<S: AsRef<str>>(&mut self, tokens: &[S]) -> String {
+        self.sentencepiece |= tokens
+            .iter()
+            .any(|token| token.as_ref().starts_with(WORD_BOUNDARY));
+        let mut text = String::new();
+        for token in tokens {
+            let token = token.as_ref();
+            if is_special_token(token) {
+                continue;
+            }
+            let (token, glue_next) = match token.strip_suffix(CONTINUATION) {
+                Some(token) => (token, true),
+                None => (token, false),
+            };
+            let token = match token.strip_prefix(WORD_BOUNDARY) {
+                Some(token) => {
+                    self.word_start = true;
+                    token
+                }
+                None => token,
+            };
+            if let Some(first) = token.chars().next() {
+                if let Some(last) = self.last {
+                    if !self.glue && needs_space(last, first, self.word_start, self.sentencepiece) {
+                        text.push(' ');
+                    }
+                }
+                text.push_str(token);
+                self.last = token.chars().last();
+                self.word_start = false;
+            }
+            // 单独的 `▁` 只标记下一个单词的开始
+            self.glue = glue_next;
+        }
+        text
+    }
+}
+
+/// 拼接 token 为文本，见 [`Detokenizer`]
+pub fn detokenize<S: AsRef<str>>(tokens: &[S]) -> String {
+    Detokenizer::default().push(tokens)
+}
+
+/// `<s>`、`</s>`、`<unk>`、`<blank>` 以及 SenseVoice 的 `<|zh|>` 等特殊 token
+fn is_special_token(token: &str) -> bool {
+    token.len() > 2 && token.starts_with('<') && token.ends_with('>')
+}
+
+/// 中文、日文的字符，字符之间不加空格
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}' // 平假名、片假名
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+    )
+}
+
+/// 全角标点，前后都不加空格
+fn is_fullwidth_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303f}' | '\u{ff00}'..='\u{ffef}')
+}
+
+/// 英文、韩文等以空格分隔单词的文字
+fn is_letter(c: char) -> bool {
+    c.is_alphabetic() && !is_cjk(c)
+}
+
+/// 单词中的字符，包括字母与数字
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() && !is_cjk(c)
+}
+
+/// 判断两个 token 之间是否需要空格
+/// `last` 为前一个 token 的最后一个字符，`first` 为后一个 token 的第一个字符
+fn needs_space(last: char, first: char, word_start: bool, sentencepiece: bool) -> bool {
+    if last.is_whitespace() || first.is_whitespace() {
+        return false;
+    }
+    if is_fullwidth_punctuation(last) || is_fullwidth_punctuation(first) {
+        return false;
+    }
+    if is_cjk(last) || is_cjk(first) {
+        // 中文与英文单词之间加空格，与数字、标点之间不加
+        return is_letter(last) || is_letter(first);
+    }
+    if !is_word(first) {
+        // 标点前不加空格
+        return false;
+    }
+    if !is_word(last) {
+        // 标点后只有新单词前加空格，如 `,▁world`
+        return word_start;
+    }
+    // BPE 的 token 不以 `@@` 结尾即为完整单词
+    word_start || !sentencepiece
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_boundary_starts_new_word() {
+        assert_eq!(detokenize(&["▁hello", "▁wor", "ld"]), "hello world");
+        // 单独的 `▁` 只标记下一个单词的开始
+        assert_eq!(detokenize(&["▁", "hi", "▁there"]), "hi there");
+    }
+
+    #[test]
+    fn continuation_merges_with_next_token() {
+        assert_eq!(detokenize(&["hel@@", "lo", "wor@@", "ld"]), "hello world");
+    }
+
+    #[test]
+    fn spaces_between_cjk_and_latin() {
+        assert_eq!(
+            detokenize(&["我", "爱", "▁python", "语", "言"]),
+            "我爱 python 语言"
+        );
+        assert_eq!(detokenize(&["我", "有", "3", "个"]), "我有3个");
+    }
+
+    #[test]
+    fn no_space_before_punctuation() {
+        assert_eq!(detokenize(&["▁hello", ",", "▁world", "."]), "hello, world.");
+        assert_eq!(detokenize(&["hello", "?"]), "hello?");
+        assert_eq!(
+            detokenize(&["你", "好", "，", "▁world", "。"]),
+            "你好，world。"
+        );
+    }
+
+    #[test]
+    fn strips_special_tokens() {
+        let tokens = [
+            "<s>",
+            "<|zh|>",
+            "<|NEUTRAL|>",
+            "<|Speech|>",
+            "你",
+            "好",
+            "</s>",
+        ];
+        assert_eq!(detokenize(&tokens), "你好");
+    }
+
+    #[test]
+    fn streaming_matches_single_push() {
+        let tokens = [
+            "<|en|>", "▁he", "llo", "▁", "wor@@", "ld", ",", "你", "好", "▁ok", "。",
+        ];
+        let expected = detokenize(&tokens);
+        assert_eq!(expected, "hello world,你好 ok。");
+        for split in 0..=tokens.len() {
+            let mut detokenizer = Detokenizer::default();
+            let mut text = detokenizer.push(&tokens[..split]);
+            text.push_str(&detokenizer.push(&tokens[split..]));
+            assert_eq!(text, expected, "在第 {} 个 token 处分开", split);
+        }
+        let mut detokenizer = Detokenizer::default();
+        let text: String = tokens
+            .iter()
+            .map(|token| detokenizer.push(&[token]))
+            .collect();
+        assert_eq!(text, expected);
+    }
+}
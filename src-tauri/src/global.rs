@@ -1,12 +1,21 @@
-use crate::funasr::{default_device, Language, Paraformer, Recorder, SenseVoice, Vad};
+use crate::funasr::utils::constant::VAD_CONFIG;
+use crate::funasr::{
+    default_device, ArchiveMode, CaptionMode, ChannelMode, CmvnConfig, Language, OverflowPolicy,
+    Paraformer, Recorder, SenseVoice, SileroVad, Vad, VadBackend, VadConfig, VadModel,
+    VadSensitivity,
+};
 use cpal::traits::DeviceTrait;
+use std::path::PathBuf;
 use std::sync::{Arc, LazyLock, Mutex};
 use cpal::Device;
+use log::{error, warn};
 
 // 将所有 LazyLock 变量初始化
 pub fn init_lazy_lock() {
     let _ = &*CONFIG;
-    let _ = &*VAD;
+    if let Err(e) = &*VAD {
+        error!("加载语音端点检测模型失败: {}", e);
+    }
     let _ = &*SENSE_VOICE;
     let _ = &*PARAFORMER;
 }
@@ -14,12 +23,37 @@ pub fn init_lazy_lock() {
 pub static CONFIG: LazyLock<Arc<Mutex<Config>>> =
     LazyLock::new(|| Arc::new(Mutex::new(Config::default())));
 
-pub static VAD: LazyLock<Arc<Mutex<Vad>>> = LazyLock::new(|| {
-    Arc::new(Mutex::new(
-        Vad::new(None).expect("Failed to create Vad instance"),
-    ))
+/// 加载失败时保存错误信息，端点检测改用能量检测
+pub static VAD: LazyLock<Result<Arc<Mutex<Vad>>, String>> = LazyLock::new(|| {
+    Vad::new(None)
+        .map(|vad| Arc::new(Mutex::new(vad)))
+        .map_err(|e| e.to_string())
 });
 
+/// Silero VAD 模型为可选模型，加载失败时保存错误信息
+pub static SILERO_VAD: LazyLock<Result<Arc<Mutex<SileroVad>>, String>> = LazyLock::new(|| {
+    SileroVad::new(None)
+        .map(|vad| Arc::new(Mutex::new(vad)))
+        .map_err(|e| e.to_string())
+});
+
+/// 加载指定的语音端点检测模型
+pub fn load_vad_backend(model: VadModel) -> Result<VadBackend, String> {
+    match model {
+        VadModel::Fsmn => VAD.clone().map(VadBackend::Fsmn),
+        VadModel::Silero => SILERO_VAD.clone().map(VadBackend::Silero),
+        VadModel::Energy => Ok(VadBackend::Energy),
+    }
+}
+
+/// 获取指定的语音端点检测模型，模型加载失败时使用能量检测
+pub fn vad_backend(model: VadModel) -> VadBackend {
+    load_vad_backend(model).unwrap_or_else(|e| {
+        warn!("语音端点检测模型 {:?} 不可用，改用能量检测: {}", model, e);
+        VadBackend::Energy
+    })
+}
+
 pub static SENSE_VOICE: LazyLock<Arc<Mutex<SenseVoice>>> = LazyLock::new(|| {
     Arc::new(Mutex::new(
         SenseVoice::new(None, None).expect("Failed to create SenseVoice instance"),
@@ -36,9 +70,30 @@ pub static STOP_SPEECH_RECOGNITION:LazyLock<Arc<Mutex<bool>>> = LazyLock::new(||
     Arc::new(Mutex::new(false))
 });
 
+#[derive(Clone)]
 pub struct Config {
     pub select_device_name: Option<String>,
     pub language: Language,
+    /// 多声道输入设备的处理方式
+    pub channel_mode: ChannelMode,
+    /// 识别速度跟不上录音时的处理方式
+    pub overflow_policy: OverflowPolicy,
+    /// 识别音频的保存方式
+    pub archive_mode: ArchiveMode,
+    /// 识别音频的保存目录
+    pub archive_dir: PathBuf,
+    /// VAD 与语音识别前端的 CMVN 方式
+    pub cmvn: CmvnConfig,
+    /// 语音端点检测的灵敏度
+    pub vad_sensitivity: VadSensitivity,
+    /// 语音端点检测配置，修改后正在进行的识别立即生效
+    pub vad_config: VadConfig,
+    /// 语音端点检测模型，下次开始识别时生效
+    pub vad_model: VadModel,
+    /// 字幕的显示方式
+    pub caption_mode: CaptionMode,
+    /// 是否将识别结果中的数字、日期、金额转为阿拉伯数字
+    pub itn: bool,
 }
 
 impl Default for Config {
@@ -58,6 +113,16 @@ impl Default for Config {
         Self {
             select_device_name: default_device_name,
             language: Language::Chinese,
+            channel_mode: ChannelMode::default(),
+            overflow_policy: OverflowPolicy::default(),
+            archive_mode: ArchiveMode::default(),
+            archive_dir: PathBuf::from("recordings"),
+            cmvn: CmvnConfig::default(),
+            vad_sensitivity: VadSensitivity::default(),
+            vad_config: VAD_CONFIG.clone(),
+            vad_model: VadModel::default(),
+            caption_mode: CaptionMode::default(),
+            itn: false,
         }
     }
 }
@@ -77,11 +142,29 @@ pub fn get_device_by_name(target_device_name: String) -> Option<Device> {
 
 
 pub static RECORDER:LazyLock<Arc<Mutex<Recorder>>> = LazyLock::new(|| {
-    let select_device_name = CONFIG.lock().unwrap().select_device_name.clone().unwrap();
-    let device = get_device_by_name(select_device_name).unwrap();
-    let recorder = Recorder::new(device);
+    let config = CONFIG.lock().unwrap().clone();
+    let recorder = open_recorder(&config);
     let recorder = Arc::new(Mutex::new(recorder));
     recorder
 });
 
+/// 按配置打开输入设备
+/// 选中的设备不可用时使用默认输入设备，都不可用时返回没有输入设备的 Recorder
+pub fn open_recorder(config: &Config) -> Recorder {
+    let devices = config
+        .select_device_name
+        .clone()
+        .and_then(get_device_by_name)
+        .into_iter()
+        .chain(default_device());
+    for device in devices {
+        match Recorder::new(device, config.channel_mode, config.overflow_policy) {
+            Ok(recorder) => return recorder,
+            Err(e) => error!("打开输入设备失败: {}", e),
+        }
+    }
+    warn!("没有可用的输入设备");
+    Recorder::unavailable()
+}
+
 
@@ -1,4 +1,5 @@
 mod command;
+mod device_watcher;
 mod funasr;
 mod global;
 mod tray_icon;
@@ -19,7 +20,20 @@ pub fn run() {
             download_models,
             init,
             start_speech_recognition,
-            stop_speech_recognition
+            stop_speech_recognition,
+            transcribe_file,
+            set_channel_mode,
+            set_overflow_policy,
+            list_device_configs,
+            set_archive_mode,
+            set_cmvn_config,
+            get_vad_settings,
+            set_vad_sensitivity,
+            set_vad_config,
+            set_vad_model,
+            set_caption_mode,
+            set_itn,
+            get_segment_audio
         ])
         .setup(|app| {
             setup_tray_icon(app);
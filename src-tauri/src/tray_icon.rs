@@ -1,7 +1,9 @@
-use crate::funasr::{devices, hosts, Language, Recorder};
-use crate::global::{get_device_by_name, CONFIG, RECORDER, SENSE_VOICE};
+use crate::device_watcher::watch_devices;
+use crate::funasr::utils::constant::VAD_CONFIG;
+use crate::funasr::{devices, hosts, Language, VadSensitivity};
+use crate::global::{open_recorder, CONFIG, RECORDER, SENSE_VOICE};
 use cpal::{traits::DeviceTrait, Device};
-use log::{debug, info};
+use log::{debug, error, info};
 use tauri::tray::MouseButton::Left;
 use tauri::tray::TrayIconEvent;
 use tauri::{
@@ -14,7 +16,23 @@ pub(crate) fn setup_tray_icon(app: &App) {
     let exit_menu = MenuItem::with_id(app, "exit", "退出", true, None::<&str>).unwrap();
     let device_menu = create_device_menu(app.handle());
     let language_menu = create_language_menu(app.handle());
-    let menu = Menu::with_items(app, &[&device_menu, &language_menu, &exit_menu]).unwrap();
+    let vad_sensitivity_menu = create_vad_sensitivity_menu(app.handle());
+    app.manage(VadSensitivityMenu(vad_sensitivity_menu.clone()));
+    let itn_menu = create_itn_menu(app.handle());
+    app.manage(ItnMenu(itn_menu.clone()));
+    let menu = Menu::with_items(
+        app,
+        &[
+            &device_menu,
+            &language_menu,
+            &vad_sensitivity_menu,
+            &itn_menu,
+            &exit_menu,
+        ],
+    )
+    .unwrap();
+    // 输入设备插拔时刷新设备菜单并自动切换设备
+    watch_devices(app.handle().clone(), device_menu.clone());
 
     TrayIconBuilder::new()
         .show_menu_on_left_click(false)
@@ -47,6 +65,11 @@ pub(crate) fn setup_tray_icon(app: &App) {
                     info!("退出程序");
                     exit(app_handle);
                 }
+                "itn" => {
+                    let itn = !CONFIG.lock().unwrap().itn;
+                    apply_itn(itn);
+                    check_itn_menu(app_handle, itn);
+                }
                 _ => {
                     if event_id.starts_with("input_device_") {
                         change_select_device_name(
@@ -57,6 +80,12 @@ pub(crate) fn setup_tray_icon(app: &App) {
                     if event_id.starts_with("language_") {
                         change_select_language(event_id.replace("language_", ""), &language_menu);
                     }
+                    if event_id.starts_with("vad_sensitivity_") {
+                        change_vad_sensitivity(
+                            app_handle,
+                            event_id.replace("vad_sensitivity_", ""),
+                        );
+                    }
                 }
             }
         })
@@ -86,10 +115,10 @@ fn device_menu_item(app: &AppHandle, device: &Device) -> Option<CheckMenuItem<Wr
     }
 
     let device_name = device.name().unwrap();
-    let select_device_name = { CONFIG.lock().unwrap().select_device_name.clone().unwrap() };
+    let select_device_name = { CONFIG.lock().unwrap().select_device_name.clone() };
     Some(
         CheckMenuItemBuilder::with_id(format!("input_device_{}", device_name), device_name.clone())
-            .checked(device_name == select_device_name)
+            .checked(Some(&device_name) == select_device_name.as_ref())
             .build(app)
             .unwrap(),
     )
@@ -97,22 +126,33 @@ fn device_menu_item(app: &AppHandle, device: &Device) -> Option<CheckMenuItem<Wr
 
 /// 创建一个输入设备菜单
 fn create_device_menu(app: &AppHandle) -> Submenu<Wry> {
+    let menu = SubmenuBuilder::with_id(app, "input_device", "输入设备")
+        .build()
+        .unwrap();
+    refresh_device_menu(app, &menu);
+    menu
+}
+
+/// 重新枚举输入设备并刷新输入设备菜单
+pub(crate) fn refresh_device_menu(app: &AppHandle, device_menu: &Submenu<Wry>) {
     let input_devices = all_input_devices();
     let device_menu_items: Vec<CheckMenuItem<Wry>> = input_devices
         .iter()
         .filter_map(|device| device_menu_item(app, device))
         .collect();
+    for item in device_menu.items().unwrap_or_default() {
+        let _ = device_menu.remove(&item);
+    }
     if device_menu_items.is_empty() {
-        return SubmenuBuilder::with_id(app, "input_device", "无可用输入设备")
-            .enabled(false)
-            .build()
-            .unwrap();
+        let _ = device_menu.set_text("无可用输入设备");
+        let _ = device_menu.set_enabled(false);
+        return;
     }
-    let mut menu = SubmenuBuilder::with_id(app, "input_device", "输入设备");
+    let _ = device_menu.set_text("输入设备");
+    let _ = device_menu.set_enabled(true);
     for item in device_menu_items {
-        menu = menu.item(&item);
+        let _ = device_menu.append(&item);
     }
-    menu.build().unwrap()
 }
 /// 退出程序
 fn exit(app: &AppHandle) {
@@ -125,14 +165,14 @@ fn exit(app: &AppHandle) {
 /// 修改选中的设备名称
 fn change_select_device_name(device_name: String, device_menu: &Submenu<Wry>) {
     // 修改配置
-    let mut config = CONFIG.lock().unwrap();
-    config.select_device_name = Some(device_name.clone());
-    // 修改 RECORDER
-    let mut recorder = RECORDER.lock().unwrap();
-    let device = get_device_by_name(device_name).unwrap();
-    let recorder_ins = Recorder::new(device);
-    *recorder = recorder_ins;
-    
+    let config = {
+        let mut config = CONFIG.lock().unwrap();
+        config.select_device_name = Some(device_name.clone());
+        config.clone()
+    };
+    // 修改 RECORDER，设备已被拔出时使用默认输入设备
+    *RECORDER.lock().unwrap() = open_recorder(&config);
+
     if let Some(select_device_name) = config.select_device_name.clone() {
         for item in device_menu.items().unwrap() {
             if let Some(check_item) = item.as_check_menuitem() {
@@ -193,3 +233,83 @@ fn change_select_language(language_name: String, language_menu: &Submenu<Wry>) {
         }
     }
 }
+
+/// 语音端点检测灵敏度菜单，保存在应用状态中，命令修改灵敏度时同步勾选状态
+pub(crate) struct VadSensitivityMenu(Submenu<Wry>);
+
+/// 创建语音端点检测灵敏度菜单
+fn create_vad_sensitivity_menu(app: &AppHandle) -> Submenu<Wry> {
+    let select_sensitivity = { CONFIG.lock().unwrap().vad_sensitivity };
+    let mut menu = SubmenuBuilder::with_id(app, "vad_sensitivity", "语音检测灵敏度");
+    for sensitivity in VadSensitivity::all() {
+        let item = CheckMenuItemBuilder::with_id(
+            format!("vad_sensitivity_{}", sensitivity.name()),
+            sensitivity.name(),
+        )
+        .checked(sensitivity == select_sensitivity)
+        .build(app)
+        .unwrap();
+        menu = menu.item(&item);
+    }
+    menu.build().unwrap()
+}
+
+/// 修改语音端点检测灵敏度
+fn change_vad_sensitivity(app: &AppHandle, sensitivity_name: String) {
+    match VadSensitivity::from_name(&sensitivity_name) {
+        Ok(sensitivity) => {
+            apply_vad_sensitivity(sensitivity);
+            check_vad_sensitivity_menu(app, sensitivity);
+        }
+        Err(e) => error!("{}", e),
+    }
+}
+
+/// 应用语音端点检测灵敏度，预设在模型目录的端点检测配置上调整，`Custom` 时保留当前配置
+pub(crate) fn apply_vad_sensitivity(sensitivity: VadSensitivity) {
+    let mut config = CONFIG.lock().unwrap();
+    config.vad_sensitivity = sensitivity;
+    if sensitivity != VadSensitivity::Custom {
+        config.vad_config = sensitivity.apply(&VAD_CONFIG);
+    }
+    info!("已切换语音检测灵敏度: {}", sensitivity.name());
+}
+
+/// 根据当前的灵敏度更新菜单的勾选状态
+pub(crate) fn check_vad_sensitivity_menu(app: &AppHandle, sensitivity: VadSensitivity) {
+    let Some(menu) = app.try_state::<VadSensitivityMenu>() else {
+        return;
+    };
+    for item in menu.0.items().unwrap_or_default() {
+        if let Some(check_item) = item.as_check_menuitem() {
+            let item_name = check_item.id().as_ref().replace("vad_sensitivity_", "");
+            let _ = check_item.set_checked(item_name == sensitivity.name());
+        }
+    }
+}
+
+/// 数字转写菜单项，保存在应用状态中，命令修改设置时同步勾选状态
+pub(crate) struct ItnMenu(CheckMenuItem<Wry>);
+
+/// 创建数字转写菜单项
+fn create_itn_menu(app: &AppHandle) -> CheckMenuItem<Wry> {
+    let itn = { CONFIG.lock().unwrap().itn };
+    CheckMenuItemBuilder::with_id("itn", "数字转写")
+        .checked(itn)
+        .build(app)
+        .unwrap()
+}
+
+/// 应用数字转写设置，SenseVoice 使用模型自带的逆文本正则化
+pub(crate) fn apply_itn(itn: bool) {
+    CONFIG.lock().unwrap().itn = itn;
+    SENSE_VOICE.lock().unwrap().itn = itn;
+    info!("已{}数字转写", if itn { "开启" } else { "关闭" });
+}
+
+/// 根据当前的设置更新数字转写菜单项的勾选状态
+pub(crate) fn check_itn_menu(app: &AppHandle, itn: bool) {
+    if let Some(menu) = app.try_state::<ItnMenu>() {
+        let _ = menu.0.set_checked(itn);
+    }
+}
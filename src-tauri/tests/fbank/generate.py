@@ -0,0 +1,131 @@
+"""生成 fbank 测试使用的参考特征
+
+参考特征必须由 kaldi-native-fbank 计算，未安装时直接报错退出。
+
+用法:
+    pip install kaldi-native-fbank
+    python generate.py
+
+生成的 JSON 文件与本脚本放在同一目录并提交，
+然后删除 src/funasr/utils/fbank.rs 中参考特征测试的 #[ignore]。
+"""
+
+import json
+import math
+import os
+import sys
+
+CASES = {
+    # FunASR 的前端配置
+    "funasr_16k_hamming": {
+        "seed": 1,
+        "samples": 4000,
+        "options": {},
+    },
+    # Kaldi 的默认配置：povey 窗、不裁剪边缘、带能量
+    "kaldi_16k_povey_no_snip": {
+        "seed": 2,
+        "samples": 3700,
+        "options": {
+            "window_type": "Povey",
+            "mel_bins": 23,
+            "snip_edges": False,
+            "use_energy": True,
+            "energy_floor": 1.0,
+        },
+    },
+    # 电话音频：8kHz、40 个梅尔滤波器、汉宁窗、相对奈奎斯特频率的最高频率
+    "telephony_8k_hann": {
+        "seed": 3,
+        "samples": 2400,
+        "options": {
+            "sample_rate": 8000,
+            "mel_bins": 40,
+            "window_type": "Hann",
+            "low_freq": 64.0,
+            "high_freq": -400.0,
+        },
+    },
+}
+
+DEFAULT_OPTIONS = {
+    "sample_rate": 16000,
+    "frame_length_ms": 25.0,
+    "frame_shift_ms": 10.0,
+    "mel_bins": 80,
+    "low_freq": 20.0,
+    "high_freq": 0.0,
+    "preemph_coeff": 0.97,
+    "window_type": "Hamming",
+    "remove_dc_offset": True,
+    "round_to_power_of_two": True,
+    "snip_edges": True,
+    "dither": 0.0,
+    "use_energy": False,
+    "energy_floor": 0.0,
+    "raw_energy": True,
+}
+
+
+def make_samples(seed, count, sample_rate):
+    """生成 16 位整数采样：两个正弦波叠加伪随机噪声"""
+    state = seed
+    samples = []
+    for n in range(count):
+        state = (state * 1103515245 + 12345) % (1 << 31)
+        noise = (state >> 16) % 2001 - 1000
+        t = n / sample_rate
+        value = 3000 * math.sin(2 * math.pi * 440 * t) + 1000 * math.sin(2 * math.pi * 1700 * t)
+        samples.append(max(-32768, min(32767, int(round(value)) + noise)))
+    return samples
+
+
+def knf_fbank(knf, options, samples):
+    opts = knf.FbankOptions()
+    opts.frame_opts.samp_freq = options["sample_rate"]
+    opts.frame_opts.frame_length_ms = options["frame_length_ms"]
+    opts.frame_opts.frame_shift_ms = options["frame_shift_ms"]
+    opts.frame_opts.preemph_coeff = options["preemph_coeff"]
+    opts.frame_opts.window_type = {"Hann": "hanning"}.get(
+        options["window_type"], options["window_type"].lower()
+    )
+    opts.frame_opts.remove_dc_offset = options["remove_dc_offset"]
+    opts.frame_opts.round_to_power_of_two = options["round_to_power_of_two"]
+    opts.frame_opts.snip_edges = options["snip_edges"]
+    opts.frame_opts.dither = options["dither"]
+    opts.mel_opts.num_bins = options["mel_bins"]
+    opts.mel_opts.low_freq = options["low_freq"]
+    opts.mel_opts.high_freq = options["high_freq"]
+    opts.use_energy = options["use_energy"]
+    opts.energy_floor = options["energy_floor"]
+    opts.raw_energy = options["raw_energy"]
+    fbank = knf.OnlineFbank(opts)
+    fbank.accept_waveform(options["sample_rate"], [float(x) for x in samples])
+    fbank.input_finished()
+    return [list(fbank.get_frame(i)) for i in range(fbank.num_frames_ready)]
+
+
+def main():
+    try:
+        import kaldi_native_fbank as knf
+    except ImportError:
+        sys.exit("需要安装 kaldi-native-fbank: pip install kaldi-native-fbank")
+    directory = os.path.dirname(os.path.abspath(__file__))
+    for name, case in CASES.items():
+        options = dict(DEFAULT_OPTIONS, **case["options"])
+        samples = make_samples(case["seed"], case["samples"], options["sample_rate"])
+        features = knf_fbank(knf, options, samples)
+        reference = {
+            "generator": "kaldi-native-fbank",
+            "options": case["options"],
+            "samples": samples,
+            "features": [[round(x, 5) for x in row] for row in features],
+        }
+        with open(os.path.join(directory, name + ".json"), "w") as f:
+            json.dump(reference, f, separators=(",", ":"))
+            f.write("\n")
+        print(name, len(features), "frames")
+
+
+if __name__ == "__main__":
+    main()